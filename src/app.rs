@@ -1,35 +1,62 @@
 use std::{
     collections::{HashMap, VecDeque},
-    fs::File,
-    io::Write,
-    sync::mpsc::{channel, Receiver, Sender},
+    io::{self, Write},
+    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
+    time::Duration,
 };
 
-use ggez::{
-    event::EventHandler,
-    glam::Vec2,
-    graphics::{Color, DrawParam, Image},
-    winit::event::VirtualKeyCode,
-    Context, GameResult,
+use crate::{
+    assets::{Assets, AVATAR_EMOTIONS},
+    canvas::{Canvas, CanvasMode},
+    context::Context,
+    countdown::Countdown,
+    debug_overlay::DebugOverlay,
+    error::Error,
+    frecency::Frecency,
+    graphics::{DrawList, Image, Vec2},
+    history::History,
+    input::{InputEvent, Key},
+    jsonrpc::{Call, JsonRpc, METHODS},
+    message::{Message, MessageId, MessageIds, MessageKind, Protocol, Response},
+    notifications::Notifications,
+    schema::{self, OLDEST_PROTOCOL_VERSION, PROTOCOL_VERSION},
+    textbox::Textbox,
+    theme::Theme,
 };
 
-use crate::{canvas::Canvas, message::Message, textbox::Textbox, SCREEN_HEIGHT, SCREEN_WIDTH};
-
 // A message sent to App when a command has finished (textbox finished displaying text or user inputted something when asked)
 pub enum FinishedMessage {
     Textbox,
-    UserInput(String),
+    UserInput(serde_json::Value),
+    // something happened that the plugin should know about, but the command is still executing (name, data)
+    Event(String, serde_json::Value),
+    // the command couldn't be done, so it's finished with an error instead of data
+    Failed(Error),
+}
+
+// A command that was interrupted, with the canvas that was showing it
+struct SuspendedCommand {
+    message: Box<Message>,
+    canvas: Canvas,
+    countdown: Option<Countdown>,
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq)]
 pub enum AppState {
     Idle,
-    ExecutingCommand(Message),
+    ExecutingCommand(Box<Message>),
 }
 
+// Everything joshu does, without anything that draws it or reads the keyboard, so it can run
+// in a window (see frontend) or without one (see headless)
 pub struct App {
-    out_pipe: Option<File>,
-    input_receiver: Receiver<Message>,
+    // None once whatever was reading the responses went away
+    output: Option<Box<dyn Write>>,
+    // the raw lines that were read, they're parsed here so invalid ones can be logged
+    input_receiver: Receiver<String>,
+    // once no more messages can come in, joshu quits after the queued ones
+    input_closed: bool,
+    // what the textbox finished (the canvas has a channel of its own)
     finished_receiver: Receiver<FinishedMessage>,
     current_state: AppState,
     default_avatar_image: String,
@@ -37,249 +64,947 @@ pub struct App {
     avatar_images: HashMap<String, Image>,
     textbox: Textbox,
     canvas: Canvas,
+    notifications: Notifications,
+    // set while the executing command has a timeout
+    countdown: Option<Countdown>,
+    theme: Theme,
     message_queue: VecDeque<Message>,
+    // commands that were interrupted, the last one is resumed first
+    suspended_commands: Vec<SuspendedCommand>,
     executed_command: bool,
+    quit_requested: bool,
+    debug_overlay: DebugOverlay,
+    protocol: Protocol,
+    // only used with Protocol::JsonRpc, for the batches that are still waiting for responses
+    jsonrpc: JsonRpc,
+    // what was submitted in InputTexts with a history_key
+    history: History,
+    // which options were picked in Selects with a remember_key
+    frecency: Frecency,
+    // set by the first message with a protocol_version
+    protocol_version: Option<u32>,
+    // the deprecation warnings for the message that's being handled, sent with its response
+    // (commands keep theirs until they finish)
+    warnings: Vec<String>,
 }
 
 impl App {
     pub fn new(
-        ctx: &mut Context,
-        input_receiver: Receiver<Message>,
-        out_pipe: Option<File>,
+        ctx: &Context,
+        input_receiver: Receiver<String>,
+        output: Box<dyn Write>,
+        assets: Assets,
+        theme: Theme,
     ) -> App {
-        let avatar_images = HashMap::from([
-            (
-                String::from("normal"),
-                Image::from_path(ctx, "/kurisu/normal.png").unwrap(),
-            ),
-            (
-                String::from("embarrassed"),
-                Image::from_path(ctx, "/kurisu/embarrassed.png").unwrap(),
-            ),
-            (
-                String::from("emotionless"),
-                Image::from_path(ctx, "/kurisu/emotionless.png").unwrap(),
-            ),
-            (
-                String::from("pleased"),
-                Image::from_path(ctx, "/kurisu/pleased.png").unwrap(),
-            ),
-            (
-                String::from("winking"),
-                Image::from_path(ctx, "/kurisu/winking.png").unwrap(),
-            ),
-            (
-                String::from("angry1"),
-                Image::from_path(ctx, "/kurisu/angry1.png").unwrap(),
-            ),
-            (
-                String::from("angry2"),
-                Image::from_path(ctx, "/kurisu/angry2.png").unwrap(),
-            ),
-            (
-                String::from("angry3"),
-                Image::from_path(ctx, "/kurisu/angry3.png").unwrap(),
-            ),
-        ]);
-
         let (finished_sender, finished_receiver): (
             Sender<FinishedMessage>,
             Receiver<FinishedMessage>,
         ) = channel();
 
+        let default_avatar_image = String::from(AVATAR_EMOTIONS[0]);
+        let avatar_images = assets.avatars;
+
+        let scaled_theme = theme.for_screen(&ctx.screen());
+
+        let avatar_image = &avatar_images[&default_avatar_image];
         let textbox = Textbox::new(
             ctx,
-            avatar_images["normal"].width() as f32,
-            finished_sender.clone(),
+            &scaled_theme.textbox_config(),
+            assets.textbox,
+            avatar_image.width() as f32 * Self::avatar_scale(ctx, avatar_image),
+            finished_sender,
         );
 
-        let canvas = Canvas::new(ctx, finished_sender.clone());
+        let canvas = Canvas::new(ctx, &scaled_theme);
+        let notifications = Notifications::new(ctx, &scaled_theme.notification_config());
 
         App {
-            out_pipe,
+            output: Some(output),
             input_receiver,
+            input_closed: false,
             finished_receiver,
             current_state: AppState::Idle,
-            default_avatar_image: String::from("normal"),
-            current_avatar_image: String::from("normal"),
+            current_avatar_image: default_avatar_image.clone(),
+            default_avatar_image,
             avatar_images,
             textbox,
             canvas,
+            notifications,
+            countdown: None,
+            theme,
             message_queue: VecDeque::new(),
+            suspended_commands: Vec::new(),
             executed_command: false,
+            quit_requested: false,
+            debug_overlay: DebugOverlay::default(),
+            protocol: Protocol::default(),
+            jsonrpc: JsonRpc::default(),
+            history: History::default(),
+            frecency: Frecency::default(),
+            protocol_version: None,
+            warnings: Vec::new(),
         }
     }
 
-    fn handle_message(&mut self, ctx: &mut Context, message: Message) {
-        if matches!(self.current_state, AppState::ExecutingCommand(_)) {
+    // Set once joshu should close (the frontend is expected to do that)
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested
+    }
+
+    pub fn current_state(&self) -> &AppState {
+        &self.current_state
+    }
+
+    pub fn set_debug_overlay(&mut self, shown: bool) {
+        self.debug_overlay.set_shown(shown);
+    }
+
+    pub fn set_history(&mut self, history: History) {
+        self.history = history;
+    }
+
+    pub fn set_frecency(&mut self, frecency: Frecency) {
+        self.frecency = frecency;
+    }
+
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol = protocol;
+    }
+
+    fn set_state(&mut self, state: AppState) {
+        match &state {
+            AppState::Idle => log::debug!("Idle"),
+            AppState::ExecutingCommand(m) => log::debug!("Executing command {:?}", m.ids.id),
+        }
+
+        // what the textbox finished for the command before isn't for this one
+        while self.finished_receiver.try_recv().is_ok() {}
+
+        self.current_state = state;
+    }
+
+    // The avatar is scaled like everything else, but never gets taller than the screen (like in a corner window)
+    fn avatar_scale(ctx: &Context, avatar_image: &Image) -> f32 {
+        let screen = ctx.screen();
+        screen
+            .scale
+            .min(screen.height / avatar_image.height() as f32)
+    }
+
+    fn handle_message(&mut self, ctx: &Context, mut message: Message) {
+        if let Err(e) = self.negotiate_protocol_version(message.protocol_version) {
+            return self.output_error(e, message.ids);
+        }
+
+        // plugins that don't say which version they're for wouldn't expect the warnings
+        if self.protocol_version.is_none() {
+            message.deprecations.clear();
+        }
+        if message.kind != MessageKind::Command {
+            self.warnings = std::mem::take(&mut message.deprecations);
+        }
+
+        self.dispatch_message(ctx, message);
+        self.warnings.clear();
+    }
+
+    fn dispatch_message(&mut self, ctx: &Context, message: Message) {
+        match message.kind {
+            MessageKind::Update => return self.update_command(ctx, message),
+            MessageKind::Finish => return self.end_command(ctx, message, "Finished".into()),
+            MessageKind::Cancel => return self.end_command(ctx, message, "Cancelled".into()),
+            MessageKind::SetOptions => return self.set_options(ctx, message),
+            MessageKind::InspectQueue => return self.inspect_queue(message),
+            MessageKind::DropQueued => return self.drop_queued(message),
+            MessageKind::ClearQueue => return self.clear_queue(message),
+            MessageKind::Notification => return self.notify(message),
+            MessageKind::Describe => return self.describe(message),
+            MessageKind::ResetRemembered => return self.reset_remembered(message),
+            MessageKind::Completions => return self.show_completions(ctx, message),
+            MessageKind::Command => {}
+        }
+
+        let executing = matches!(self.current_state, AppState::ExecutingCommand(_));
+
+        if executing && !message.interrupt {
             // if there's a command currently executing, add the message to the queue
-            self.message_queue.push_back(message);
+            self.queue_message(message);
+            return;
+        }
+
+        // the message's theme only changes the keys it gives, for this command only
+        let theme = match &message.theme {
+            Some(overrides) => match self.theme.with_overrides(overrides) {
+                Ok(theme) => theme,
+                Err(e) => return self.output_error(e, message.ids),
+            },
+            None => self.theme.clone(),
+        };
+        let theme = theme.for_screen(&ctx.screen());
+
+        if executing {
+            self.suspend_command(ctx);
+        }
+
+        self.textbox.set_config(ctx, &theme.textbox_config());
+        self.canvas.set_theme(ctx, &theme);
+
+        self.set_state(AppState::ExecutingCommand(Box::new(message.clone())));
+
+        self.countdown = message.timeout_ms.map(|timeout_ms| {
+            Countdown::new(
+                ctx.clock(),
+                &theme.countdown,
+                Duration::from_millis(timeout_ms),
+                message.countdown,
+            )
+        });
+
+        // handle textbox_text inside message
+        match message.textbox_text {
+            Some(text) => {
+                // only enable finished_listener for the textbox if there's no canvas_mode in the message
+                let finished_sender_enabled = message.canvas_mode.is_none();
+                self.textbox.set_text(&text, finished_sender_enabled);
+            }
+            None => self.textbox.hide(),
+        }
+
+        // handle canvas_mode inside message
+        self.canvas.set_mode(ctx, message.canvas_mode);
+        self.canvas
+            .set_completion(message.completion.unwrap_or_default());
+        self.load_remembered(
+            message.history_key.as_deref(),
+            message.remember_key.as_deref(),
+        );
+
+        // handle avatar image
+        if let Some(emotion) = message.avatar_emotion {
+            if self.avatar_images.contains_key(&emotion) {
+                self.current_avatar_image = emotion;
+            }
+        }
+    }
+
+    // The first message that gives a protocol_version sets it, later ones have to give the same one
+    // (or none at all)
+    fn negotiate_protocol_version(&mut self, requested: Option<u32>) -> Result<(), String> {
+        let requested = match requested {
+            Some(requested) => requested,
+            None => return Ok(()),
+        };
+
+        if !(OLDEST_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&requested) {
+            return Err(format!(
+                "protocol_version {} isn't supported, only {} to {} are",
+                requested, OLDEST_PROTOCOL_VERSION, PROTOCOL_VERSION
+            ));
+        }
+
+        match self.protocol_version {
+            Some(negotiated) if negotiated != requested => Err(format!(
+                "protocol_version {} was already negotiated",
+                negotiated
+            )),
+
+            _ => {
+                log::info!("Using protocol version {}", requested);
+                self.protocol_version = Some(requested);
+                Ok(())
+            }
+        }
+    }
+
+    fn describe(&mut self, message: Message) {
+        let mut description =
+            schema::description(self.protocol_version.unwrap_or(PROTOCOL_VERSION));
+        if self.protocol == Protocol::JsonRpc {
+            description["methods"] = serde_json::json!(METHODS);
+        }
+
+        self.output_message(description, message.ids);
+    }
+
+    // Gives the InputText the message shows what was submitted before with the same history_key,
+    // or the Select it shows how often its options were picked before with the same remember_key
+    fn load_remembered(&mut self, history_key: Option<&str>, remember_key: Option<&str>) {
+        match (&self.canvas.current_mode, history_key, remember_key) {
+            (Some(CanvasMode::InputText), Some(key), _) => {
+                let history = self.history.entries(key).to_vec();
+                self.canvas.handler_input_text.set_history(history);
+            }
+
+            (Some(CanvasMode::Select(_)), _, Some(key)) => {
+                let scores = self.frecency.scores(key, Frecency::now());
+                self.canvas.handler_select.set_scores(scores);
+            }
+
+            _ => {}
+        }
+    }
+
+    // Remembers what was submitted in the executing command's InputText or picked in its Select
+    fn remember_input(&mut self, value: &serde_json::Value) {
+        let message = match &self.current_state {
+            AppState::ExecutingCommand(m) => m,
+            AppState::Idle => return,
+        };
+        let value = match value.as_str() {
+            Some(value) => value,
+            None => return,
+        };
+
+        match (
+            &self.canvas.current_mode,
+            &message.history_key,
+            &message.remember_key,
+        ) {
+            (Some(CanvasMode::InputText), Some(key), _) => self.history.add(key, value),
+
+            (Some(CanvasMode::Select(_)), _, Some(key)) => {
+                self.frecency.record(key, value, Frecency::now())
+            }
+
+            _ => {}
+        }
+    }
+
+    fn reset_remembered(&mut self, message: Message) {
+        if self.frecency.reset(message.remember_key.as_deref()) {
+            self.output_message("Reset".into(), message.ids);
         } else {
-            self.current_state = AppState::ExecutingCommand(message.clone());
-
-            // handle textbox_text inside message
-            match message.textbox_text {
-                Some(text) => {
-                    // only enable finished_listener for the textbox if there's no canvas_mode in the message
-                    let finished_sender_enabled = message.canvas_mode.is_none();
-                    self.textbox.set_text(ctx, &text, finished_sender_enabled);
-                }
-                None => self.textbox.hide(),
+            let error = match &message.remember_key {
+                Some(key) => format!("Nothing is remembered under {}", key),
+                None => String::from("Nothing is remembered"),
+            };
+            self.output_error(error, message.ids);
+        }
+    }
+
+    // Higher priority messages go first, ones with the same priority in the order they came in
+    fn queue_message(&mut self, message: Message) {
+        let position = self
+            .message_queue
+            .iter()
+            .position(|m| m.priority < message.priority)
+            .unwrap_or(self.message_queue.len());
+
+        log::debug!(
+            "Queued command {:?} at {}, {} in the queue",
+            message.ids.id,
+            position,
+            self.message_queue.len() + 1
+        );
+        self.message_queue.insert(position, message);
+    }
+
+    // Puts the executing command aside (with everything its canvas shows, like typed text)
+    // until the one that interrupted it is done, which is started right after this
+    fn suspend_command(&mut self, ctx: &Context) {
+        let message = match &self.current_state {
+            AppState::ExecutingCommand(m) => m.clone(),
+            AppState::Idle => return,
+        };
+
+        self.output_event(String::from("suspended"), serde_json::Value::Null);
+
+        let canvas = Canvas::new(ctx, &self.theme.for_screen(&ctx.screen()));
+        self.suspended_commands.push(SuspendedCommand {
+            message,
+            canvas: std::mem::replace(&mut self.canvas, canvas),
+            countdown: self.countdown.take(),
+        });
+        self.textbox.hide();
+    }
+
+    // Continues the last suspended command where it was left (its text is shown again from the start)
+    fn resume_command(&mut self, ctx: &Context, suspended: SuspendedCommand) {
+        let message = suspended.message;
+        self.canvas = suspended.canvas;
+        self.countdown = suspended.countdown;
+        if let Some(countdown) = &mut self.countdown {
+            countdown.resume();
+        }
+
+        self.textbox
+            .set_config(ctx, &self.canvas.theme.textbox_config());
+        match &message.textbox_text {
+            Some(text) => self.textbox.set_text(text, message.canvas_mode.is_none()),
+            None => self.textbox.hide(),
+        }
+
+        self.current_avatar_image = message
+            .avatar_emotion
+            .clone()
+            .filter(|emotion| self.avatar_images.contains_key(emotion))
+            .unwrap_or(self.default_avatar_image.clone());
+
+        self.set_state(AppState::ExecutingCommand(message));
+        self.output_event(String::from("resumed"), serde_json::Value::Null);
+    }
+
+    fn notify(&mut self, message: Message) {
+        let text = match &message.textbox_text {
+            Some(text) => text,
+            None => {
+                let error = String::from("A notification needs textbox_text");
+                return self.output_error(error, message.ids);
             }
+        };
+
+        let avatar = message
+            .avatar_emotion
+            .as_ref()
+            .and_then(|emotion| self.avatar_images.get(emotion))
+            .cloned();
+
+        self.notifications.show(
+            message.ids.clone(),
+            text,
+            avatar,
+            message.duration_ms.map(Duration::from_millis),
+            message.respond,
+        );
+    }
 
-            // handle canvas_mode inside message
-            self.canvas.set_mode(ctx, message.canvas_mode);
+    // Responds with what's executing, suspended and queued
+    fn inspect_queue(&mut self, message: Message) {
+        let executing = match &self.current_state {
+            AppState::ExecutingCommand(m) => m.ids.id.clone(),
+            AppState::Idle => None,
+        };
+        let suspended: Vec<_> = self
+            .suspended_commands
+            .iter()
+            .map(|s| s.message.ids.id.clone())
+            .collect();
+        let queued: Vec<_> = self
+            .message_queue
+            .iter()
+            .map(|m| serde_json::json!({ "id": m.ids.id, "priority": m.priority }))
+            .collect();
+
+        self.output_message(
+            serde_json::json!({
+                "executing": executing,
+                "suspended": suspended,
+                "queued": queued,
+            }),
+            message.ids,
+        );
+    }
 
-            // handle avatar image
-            match message.avatar_emotion {
-                Some(emotion) => {
-                    if self.avatar_images.contains_key(&emotion) {
-                        self.current_avatar_image = emotion;
-                    }
-                }
-                None => {}
+    // Removes the queued commands with the message's id
+    fn drop_queued(&mut self, message: Message) {
+        let id = match &message.ids.id {
+            Some(id) => id,
+            None => {
+                let error = String::from("An id is needed to refer to a queued command");
+                return self.output_error(error, message.ids);
+            }
+        };
+
+        let queue_length = self.message_queue.len();
+        self.message_queue.retain(|m| m.ids.id.as_ref() != Some(id));
+
+        if self.message_queue.len() == queue_length {
+            let error = format!("No queued command with id {}", id);
+            return self.output_error(error, message.ids);
+        }
+
+        self.output_message("Dropped".into(), message.ids);
+    }
+
+    // Removes every queued command, each one with an id gets a response saying it was dropped.
+    // The message itself gets how many were dropped
+    fn clear_queue(&mut self, message: Message) {
+        let dropped: Vec<_> = self.message_queue.drain(..).collect();
+
+        for m in &dropped {
+            if m.ids.id.is_some() {
+                self.output_message("Dropped".into(), m.ids.clone());
             }
         }
+
+        self.output_message(dropped.len().into(), message.ids);
     }
 
-    fn make_finished_message(data: String, id: Option<String>) -> String {
+    // Changes what the currently executing command shows, if the message's id matches it
+    fn update_command(&mut self, ctx: &Context, message: Message) {
+        let current_message = match &mut self.current_state {
+            AppState::ExecutingCommand(m) if m.ids.id.is_some() && m.ids.id == message.ids.id => m,
+            _ => return self.output_error(Self::not_executing_error(&message.ids.id), message.ids),
+        };
+
+        if let Some(emotion) = message.avatar_emotion {
+            if self.avatar_images.contains_key(&emotion) {
+                self.current_avatar_image = emotion.clone();
+                current_message.avatar_emotion = Some(emotion);
+            }
+        }
+
+        if let Some(mode) = message.canvas_mode {
+            self.canvas.patch_mode(ctx, mode);
+            current_message.canvas_mode = self.canvas.current_mode.clone();
+        }
+
+        if let Some(text) = message.textbox_text {
+            let finished_sender_enabled = current_message.canvas_mode.is_none();
+            self.textbox.set_text(&text, finished_sender_enabled);
+            current_message.textbox_text = Some(text);
+        }
+
+        if message.default.is_some() {
+            current_message.default = message.default;
+        }
+
+        // a new timeout starts over
+        if let Some(timeout_ms) = message.timeout_ms {
+            current_message.timeout_ms = Some(timeout_ms);
+            current_message.countdown |= message.countdown;
+
+            self.countdown = Some(Countdown::new(
+                ctx.clock(),
+                &self.canvas.theme.countdown,
+                Duration::from_millis(timeout_ms),
+                current_message.countdown,
+            ));
+        }
+
+        self.output_message("Updated".into(), message.ids);
+    }
+
+    // Replaces the options of the Select shown by the currently executing command, if the message's id matches it
+    fn set_options(&mut self, ctx: &Context, message: Message) {
+        match &self.current_state {
+            AppState::ExecutingCommand(m) if m.ids.id.is_some() && m.ids.id == message.ids.id => {}
+            _ => return self.output_error(Self::not_executing_error(&message.ids.id), message.ids),
+        }
+
+        if !matches!(self.canvas.current_mode, Some(CanvasMode::Select(_))) {
+            let error = String::from("The executing command isn't showing a Select");
+            return self.output_error(error, message.ids);
+        }
+
+        let options = message.options.unwrap_or_default();
+        self.canvas
+            .patch_mode(ctx, CanvasMode::Select(options.into()));
+
+        self.output_message("Updated".into(), message.ids);
+    }
+
+    // Shows the completions a plugin sent for the text of the executing command's InputText
+    // (or the focused one in its layout), if the message's id matches it
+    fn show_completions(&mut self, ctx: &Context, message: Message) {
+        match &self.current_state {
+            AppState::ExecutingCommand(m) if m.ids.id.is_some() && m.ids.id == message.ids.id => {}
+            _ => return self.output_error(Self::not_executing_error(&message.ids.id), message.ids),
+        }
+
+        let completions = message.options.unwrap_or_default();
+        if !self.canvas.show_completions(ctx, completions) {
+            let error = String::from("The executing command isn't showing an InputText");
+            return self.output_error(error, message.ids);
+        }
+
+        self.output_message("Updated".into(), message.ids);
+    }
+
+    // Finishes the currently executing command early, if the message's id matches it
+    fn end_command(&mut self, ctx: &Context, message: Message, data: serde_json::Value) {
+        match &self.current_state {
+            AppState::ExecutingCommand(m) if m.ids.id.is_some() && m.ids.id == message.ids.id => {
+                self.textbox.hide();
+                self.canvas.set_mode(ctx, None);
+                self.finish_command(data, false);
+            }
+
+            _ => self.output_error(Self::not_executing_error(&message.ids.id), message.ids),
+        }
+    }
+
+    fn not_executing_error(id: &Option<MessageId>) -> String {
         match id {
-            Some(id) => format!("{{\"id\": \"{}\", \"data\": \"{}\"}}", id, data),
-            None => format!("{{\"data\": \"{}\"}}", data),
+            Some(id) => format!("No command with id {} is executing", id),
+            None => String::from("An id is needed to refer to the executing command"),
         }
     }
 
-    fn output_message(&mut self, data: String, id: Option<String>) {
-        let message = format!("{}\n", Self::make_finished_message(data, id));
+    // Returns to the idle state and sends the executing command's response
+    // timed_out is set when the command is finished because its timeout passed
+    fn finish_command(&mut self, data: serde_json::Value, timed_out: bool) {
+        let executed_command_message = match self.end_executing() {
+            Some(message) => message,
+            None => return,
+        };
+
+        self.output_response(Response {
+            ids: executed_command_message.ids,
+            data,
+            error: None,
+            event: None,
+            timed_out,
+            warnings: executed_command_message.deprecations,
+        });
+    }
+
+    // Returns to the idle state and sends the executing command's response with an error
+    fn fail_command(&mut self, error: String) {
+        let executed_command_message = match self.end_executing() {
+            Some(message) => message,
+            None => return,
+        };
+
+        log::warn!(
+            "Command {:?} failed: {}",
+            executed_command_message.ids.id,
+            error
+        );
+        self.output_response(Response {
+            ids: executed_command_message.ids,
+            data: serde_json::Value::Null,
+            error: Some(error),
+            event: None,
+            timed_out: false,
+            warnings: executed_command_message.deprecations,
+        });
+    }
+
+    // Returns to the idle state, giving back the command that was executing (if there was one)
+    fn end_executing(&mut self) -> Option<Box<Message>> {
+        let executed_command_message = match &self.current_state {
+            // a command that was finished early (by a Finish or Cancel message) can still send its own finished message later
+            AppState::Idle => return None,
+            AppState::ExecutingCommand(m) => m.clone(),
+        };
+
+        // return to idle state
+        self.set_state(AppState::Idle);
+        self.countdown = None;
+
+        // reset avatar image
+        self.current_avatar_image = self.default_avatar_image.clone();
+
+        // marks that at least 1 command has been executed
+        self.executed_command = true;
+
+        Some(executed_command_message)
+    }
 
-        match self.out_pipe.as_mut() {
-            Some(out_pipe) => out_pipe.write_all(message.as_bytes()).unwrap(),
+    // Closes what the executing command shows, and responds with its default
+    fn time_out_command(&mut self, ctx: &Context) {
+        let default = match &self.current_state {
+            AppState::ExecutingCommand(m) => m.default.clone().unwrap_or_default(),
+            AppState::Idle => return,
+        };
 
-            None => print!("{}", message),
+        self.textbox.hide();
+        self.canvas.set_mode(ctx, None);
+        self.finish_command(default, true);
+    }
+
+    fn output_message(&mut self, data: serde_json::Value, ids: MessageIds) {
+        self.output_response(Response {
+            ids,
+            data,
+            error: None,
+            event: None,
+            timed_out: false,
+            warnings: Vec::new(),
+        });
+    }
+
+    fn output_error(&mut self, error: String, ids: MessageIds) {
+        log::warn!("Error for message {:?}: {}", ids.id, error);
+
+        self.output_response(Response {
+            ids,
+            data: serde_json::Value::Null,
+            error: Some(error),
+            event: None,
+            timed_out: false,
+            warnings: Vec::new(),
+        });
+    }
+
+    // Sends an event for the executing command, without finishing it
+    fn output_event(&mut self, event: String, data: serde_json::Value) {
+        let ids = match &self.current_state {
+            AppState::ExecutingCommand(m) => m.ids.clone(),
+            AppState::Idle => return,
+        };
+
+        self.output_response(Response {
+            ids,
+            data,
+            error: None,
+            event: Some(event),
+            timed_out: false,
+            warnings: Vec::new(),
+        });
+    }
+
+    fn output_response(&mut self, mut response: Response) {
+        // the warnings go with the message's response, not with events
+        if response.event.is_none() && response.warnings.is_empty() {
+            response.warnings = std::mem::take(&mut self.warnings);
+        }
+
+        match self.protocol {
+            Protocol::Joshu => self.write_line(serde_json::to_string(&response).unwrap()),
+
+            Protocol::JsonRpc => {
+                if let Some(response) = JsonRpc::response(&response) {
+                    self.output_jsonrpc(response);
+                }
+            }
         }
     }
-}
 
-impl EventHandler for App {
-    fn update(&mut self, ctx: &mut Context) -> GameResult {
-        // input was received
-        match self.input_receiver.try_recv() {
-            Ok(message) => self.handle_message(ctx, message),
+    // Responses that are part of a batch are held back until the whole batch can be written
+    fn output_jsonrpc(&mut self, response: serde_json::Value) {
+        for line in self.jsonrpc.respond(response) {
+            self.write_line(line);
+        }
+    }
+
+    fn write_line(&mut self, line: String) {
+        let output = match &mut self.output {
+            Some(output) => output,
+            None => return,
+        };
+
+        let message = format!("{}\n", line);
+
+        match output
+            .write_all(message.as_bytes())
+            .and_then(|_| output.flush())
+        {
+            Ok(()) => {}
 
-            Err(std::sync::mpsc::TryRecvError::Empty) => {}
-            Err(std::sync::mpsc::TryRecvError::Disconnected) => panic!("wtf just happened??"),
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                log::warn!("The output was closed, responses won't be written anymore");
+                self.output = None;
+            }
+
+            Err(e) => log::error!("Could not write response {}: {}", message.trim_end(), e),
+        }
+    }
+
+    // Parses a line that was read, invalid ones are logged (and responded to with an error, if they're json)
+    fn handle_input_line(&mut self, ctx: &Context, line: String) {
+        log::debug!("Received {}", line.trim_end());
+        self.debug_overlay.input(&line);
+
+        if self.protocol == Protocol::JsonRpc {
+            return self.handle_jsonrpc_line(ctx, &line);
         }
 
-        // command has finished
-        match self.finished_receiver.try_recv() {
-            Ok(message) => {
-                let executed_command_message = match &self.current_state {
-                    AppState::Idle => unreachable!(),
-                    AppState::ExecutingCommand(m) => m.clone(),
-                };
+        match serde_json::from_str::<Message>(&line) {
+            Ok(mut message) => {
+                if let Ok(value) = serde_json::from_str::<serde_json::Value>(&line) {
+                    message.deprecations = schema::deprecations(&value);
+                }
+                log_deprecations(&message);
+                self.handle_message(ctx, message)
+            }
 
-                // return to idle state
-                self.current_state = AppState::Idle;
+            Err(e) => {
+                log::warn!("Invalid message {}: {}", line.trim_end(), e);
+
+                // if it's json at all, the plugin is probably waiting for a response to it
+                if let Ok(value) = serde_json::from_str::<serde_json::Value>(&line) {
+                    let error = format!("Invalid message: {}", e);
+                    self.output_error(error, MessageIds::from_json(&value));
+                }
+            }
+        }
+    }
+
+    fn handle_jsonrpc_line(&mut self, ctx: &Context, line: &str) {
+        for call in self.jsonrpc.parse(line) {
+            match call {
+                Ok(Call::Message(message)) => {
+                    log_deprecations(&message);
+                    self.handle_message(ctx, *message)
+                }
+                Ok(Call::Quit(ids)) => {
+                    self.quit();
+                    self.output_message("Quitting".into(), ids);
+                }
+
+                Err(e) => {
+                    log::warn!("Invalid request {}: {}", line.trim_end(), e.message);
+                    self.output_jsonrpc(e.to_json());
+                }
+            }
+        }
+
+        // batches of requests that were all responded to straight away (or didn't need responses)
+        for line in self.jsonrpc.finished_batches() {
+            self.write_line(line);
+        }
+    }
+
+    // Tells the executing and suspended commands that joshu is quitting, and asks the frontend to close
+    fn quit(&mut self) {
+        // if a command was executing, then send a message saying that joshu is quitting
+        // but maybe the command that's currently executing was submitted by a message with an id, so extract that here first
+        let mut ids = Vec::new();
+        if let AppState::ExecutingCommand(message) = &self.current_state {
+            ids.push(message.ids.clone());
+        };
+        // suspended commands were started too, so they get the same message
+        ids.extend(
+            self.suspended_commands
+                .iter()
+                .rev()
+                .map(|s| s.message.ids.clone()),
+        );
+
+        for ids in ids.into_iter().filter(|ids| ids.id.is_some()) {
+            self.output_message("Quitting...".into(), ids);
+        }
+
+        self.quit_requested = true;
+    }
 
-                // reset avatar image
-                self.current_avatar_image = self.default_avatar_image.clone();
+    // What the textbox or the executing command's canvas sent, if anything
+    fn next_finished(&mut self) -> Option<FinishedMessage> {
+        self.finished_receiver
+            .try_recv()
+            .ok()
+            .or_else(|| self.canvas.next_finished())
+    }
 
-                // marks that at least 1 command has been executed
-                self.executed_command = true;
+    pub fn update(&mut self, ctx: &Context) {
+        self.debug_overlay.frame(ctx.clock().time_since_start());
 
-                let finished_message = match message {
-                    FinishedMessage::Textbox => format!("Finished displaying text"),
-                    FinishedMessage::UserInput(str) => {
-                        self.canvas.set_mode(ctx, None);
-                        format!("{}", str)
-                    }
-                };
+        // input was received
+        match self.input_receiver.try_recv() {
+            Ok(line) => self.handle_input_line(ctx, line),
 
-                self.output_message(finished_message, executed_command_message.id);
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => {
+                if !self.input_closed {
+                    log::info!("The input was closed, quitting once the queued commands are done");
+                    self.input_closed = true;
+                }
             }
+        }
+
+        // command has finished (or sent events). Once it's finished, the rest of what its canvas
+        // sent is dropped when the mode is cleared, so it can't finish the next command too
+        while let Some(message) = self.next_finished() {
+            match message {
+                FinishedMessage::Event(event, data) => self.output_event(event, data),
+
+                FinishedMessage::Failed(e) => {
+                    self.canvas.set_mode(ctx, None);
+                    self.fail_command(e.to_string());
+                }
 
-            Err(std::sync::mpsc::TryRecvError::Empty) => {}
-            Err(std::sync::mpsc::TryRecvError::Disconnected) => panic!("wtf just happened??"),
+                FinishedMessage::Textbox => {
+                    self.finish_command("Finished displaying text".into(), false)
+                }
+
+                FinishedMessage::UserInput(value) => {
+                    self.remember_input(&value);
+                    self.canvas.set_mode(ctx, None);
+                    self.finish_command(value, false);
+                }
+            }
         }
 
         self.textbox.update(ctx);
+        self.canvas.update(ctx);
+
+        let timed_out = self.countdown.as_mut().is_some_and(|countdown| {
+            countdown.update();
+            countdown.is_over()
+        });
+        if timed_out {
+            self.time_out_command(ctx);
+        }
+
+        for id in self.notifications.update() {
+            self.output_message("Dismissed".into(), id);
+        }
 
         let idle = self.current_state == AppState::Idle;
         if idle {
-            let messages_left = !self.message_queue.is_empty();
-
-            if messages_left {
-                let message = self.message_queue.pop_front().unwrap();
+            if let Some(suspended) = self.suspended_commands.pop() {
+                self.resume_command(ctx, suspended);
+            } else if let Some(message) = self.message_queue.pop_front() {
                 self.handle_message(ctx, message);
-            } else if self.executed_command {
-                // if there's no more commands to execute, and at least 1 has been executed already, then quit
-                ctx.request_quit();
+            } else if (self.executed_command || self.input_closed) && self.notifications.is_empty()
+            {
+                // if there's no more commands to execute or notifications to show, and at least 1 command
+                // has been executed already (or none can come anymore), then quit
+                self.quit_requested = true;
             }
         }
-
-        Ok(())
     }
 
-    fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        let mut canvas = ggez::graphics::Canvas::from_frame(ctx, Color::new(0.0, 0.0, 0.0, 0.0));
-
-        self.textbox.draw(&mut canvas);
+    pub fn draw(&self, ctx: &Context, draw_list: &mut DrawList) {
+        self.textbox.draw(ctx, draw_list);
 
         // draw avatar
         let avatar_image = &self.avatar_images[&self.current_avatar_image];
-        canvas.draw(
+        let screen = ctx.screen();
+        let scale = Self::avatar_scale(ctx, avatar_image);
+        draw_list.image(
             avatar_image,
-            DrawParam::new().dest(Vec2::new(
-                SCREEN_WIDTH - avatar_image.width() as f32,
-                SCREEN_HEIGHT - avatar_image.height() as f32,
-            )),
+            Vec2::new(
+                screen.width - avatar_image.width() as f32 * scale,
+                screen.height - avatar_image.height() as f32 * scale,
+            ),
+            Vec2::new(scale, scale),
         );
 
-        self.canvas.draw(ctx, &mut canvas);
-
-        canvas.finish(ctx)
+        self.canvas.draw(ctx, draw_list);
+        if let Some(countdown) = &self.countdown {
+            countdown.draw(draw_list, self.canvas.bounds());
+        }
+        self.notifications.draw(ctx, draw_list);
+
+        let message_id = match &self.current_state {
+            AppState::ExecutingCommand(m) => m.ids.id.as_ref(),
+            AppState::Idle => None,
+        };
+        self.debug_overlay
+            .draw(ctx, draw_list, message_id, self.message_queue.len());
     }
 
-    fn key_down_event(
-        &mut self,
-        ctx: &mut Context,
-        input: ggez::input::keyboard::KeyInput,
-        _repeated: bool,
-    ) -> Result<(), ggez::GameError> {
-        match input.keycode {
-            Some(VirtualKeyCode::Back) => self.canvas.handle_backspace(ctx),
+    pub fn handle_input(&mut self, ctx: &Context, event: InputEvent) {
+        match event {
+            InputEvent::Key(Key::Backspace) => self.canvas.handle_backspace(ctx),
 
-            Some(VirtualKeyCode::Return) => self.canvas.handle_enter(&ctx),
+            InputEvent::Key(Key::Enter) => self.canvas.handle_enter(ctx),
 
-            Some(VirtualKeyCode::Escape) => {
-                // if a command was executing, then send a message saying that joshu is quitting
-                // but maybe the command that's currently executing was submitted by a message with an id, so extract that here first
-                match &self.current_state {
-                    AppState::ExecutingCommand(Message { id: Some(id), .. }) => {
-                        self.output_message(format!("Quitting..."), Some(id.clone()));
-                    }
+            InputEvent::Key(Key::Tab) => self.canvas.handle_tab(ctx),
 
-                    _ => {}
-                };
+            InputEvent::Key(Key::Escape) => self.quit(),
 
-                ctx.request_quit();
-            }
+            InputEvent::Key(Key::F12) => self.debug_overlay.toggle(),
 
-            Some(VirtualKeyCode::Left)
-            | Some(VirtualKeyCode::Right)
-            | Some(VirtualKeyCode::Up)
-            | Some(VirtualKeyCode::Down) => {
-                self.canvas.handle_arrow_key(&ctx, input.keycode.unwrap())
-            }
+            InputEvent::Key(key) => self.canvas.handle_arrow_key(ctx, key),
 
-            _ => {}
+            InputEvent::Shortcut(shortcut) => self.canvas.handle_shortcut(ctx, shortcut),
+
+            InputEvent::Text(ch) => self.canvas.handle_text_input(ctx, ch),
         }
-        Ok(())
     }
+}
 
-    fn text_input_event(&mut self, ctx: &mut Context, ch: char) -> Result<(), ggez::GameError> {
-        self.canvas.handle_text_input(ctx, ch);
-
-        Ok(())
+// Logged once when the message is read, since queued messages are handled again later
+fn log_deprecations(message: &Message) {
+    if !message.deprecations.is_empty() {
+        log::warn!(
+            "Message {:?} uses deprecated forms: {}",
+            message.ids.id,
+            message.deprecations.join(", ")
+        );
     }
 }
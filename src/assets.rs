@@ -0,0 +1,75 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{error::Error, graphics::Image};
+
+// The emotions there's an avatar image for (res/kurisu/<emotion>.png), the first one is the default
+pub const AVATAR_EMOTIONS: [&str; 8] = [
+    "normal",
+    "embarrassed",
+    "emotionless",
+    "pleased",
+    "winking",
+    "angry1",
+    "angry2",
+    "angry3",
+];
+
+// The images in the resource directory
+#[derive(Debug, Clone)]
+pub struct Assets {
+    pub textbox: Image,
+    pub avatars: HashMap<String, Image>,
+}
+
+impl Assets {
+    const TEXTBOX_SIZE: (u32, u32) = (1920, 328);
+    const AVATAR_SIZE: (u32, u32) = (600, 696);
+
+    // Images that can't be loaded are logged and left blank, so joshu still runs without them
+    pub fn load(res_dir: &Path) -> Self {
+        let load_image = |path: PathBuf, (width, height)| match Self::load_image(&path) {
+            Ok(image) => image,
+            Err(e) => {
+                log::warn!("{}, it won't be shown", e);
+                Image::blank(width, height)
+            }
+        };
+
+        let mut avatars = HashMap::new();
+        for emotion in AVATAR_EMOTIONS {
+            let path = res_dir.join("kurisu").join(format!("{}.png", emotion));
+            avatars.insert(emotion.to_string(), load_image(path, Self::AVATAR_SIZE));
+        }
+
+        Self {
+            textbox: load_image(res_dir.join("textbox.png"), Self::TEXTBOX_SIZE),
+            avatars,
+        }
+    }
+
+    fn load_image(path: &Path) -> Result<Image, Error> {
+        let error = |reason: String| Error::Asset {
+            path: path.to_path_buf(),
+            reason,
+        };
+
+        let bytes = std::fs::read(path).map_err(|e| error(e.to_string()))?;
+        Image::from_bytes(&bytes).map_err(error)
+    }
+
+    // Images that are as big as the real ones but have nothing in them, for when nothing's drawn
+    pub fn blank() -> Self {
+        let (width, height) = Self::AVATAR_SIZE;
+
+        Self {
+            textbox: Image::blank(Self::TEXTBOX_SIZE.0, Self::TEXTBOX_SIZE.1),
+            avatars: AVATAR_EMOTIONS
+                .iter()
+                .map(|emotion| (emotion.to_string(), Image::blank(width, height)))
+                .collect(),
+        }
+    }
+}
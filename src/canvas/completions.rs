@@ -0,0 +1,142 @@
+use std::sync::mpsc::Sender;
+
+use crate::{
+    app::FinishedMessage,
+    completion::{self, Completion},
+    context::Context,
+    graphics::{DrawList, Rect, Vec2},
+    input::Key,
+};
+
+use super::{
+    input_text::InputTextHandler,
+    select::{SelectConfig, SelectData, SelectHandler},
+    CanvasModeHandler,
+};
+
+// The completions Tab found for an InputText, shown as a Select whose input field is drawn
+// right over the InputText's (with the same text), so it looks like the options drop down from it.
+// The canvas has one for its InputText, and every layout one for its InputText children
+pub struct Completions {
+    select: SelectHandler,
+    finished_sender: Sender<FinishedMessage>,
+    pub shown: bool,
+    // where they come from
+    pub completion: Completion,
+}
+
+impl Completions {
+    pub fn new(
+        ctx: &Context,
+        config: &SelectConfig,
+        finished_sender: Sender<FinishedMessage>,
+    ) -> Self {
+        Self {
+            select: SelectHandler::new(ctx, config, finished_sender.clone()),
+            finished_sender,
+            shown: false,
+            completion: Completion::default(),
+        }
+    }
+
+    // Tab in an InputText: finds what its text can be completed to, or goes to the next completion
+    // if they're already shown. Returns whether Tab was used (in a layout, it moves focus otherwise)
+    pub fn complete(&mut self, ctx: &Context, input: &mut InputTextHandler) -> bool {
+        if self.shown {
+            self.select.handle_arrow_key(ctx, Key::Down);
+            return true;
+        }
+
+        let text = &input.entire_text;
+        match self.completion {
+            Completion::Path => {
+                // a path that's already complete only completes to itself, which isn't a use
+                let paths = completion::complete_path(text);
+                let found = paths.iter().any(|path| path != text);
+                self.show(ctx, input, paths);
+                found
+            }
+
+            // the caret is always at the end for now, but plugins shouldn't have to assume that
+            Completion::Plugin => {
+                self.finished_sender
+                    .send(FinishedMessage::Event(
+                        String::from("complete"),
+                        serde_json::json!({ "text": text, "caret": text.chars().count() }),
+                    ))
+                    .unwrap();
+                true
+            }
+
+            Completion::Off => false,
+        }
+    }
+
+    // Shows the completions for what's typed in input. A single one is inserted straight away
+    pub fn show(&mut self, ctx: &Context, input: &mut InputTextHandler, completions: Vec<String>) {
+        match completions.len() {
+            0 => return self.hide(),
+            1 => {
+                input.set_text(ctx, &completions[0]);
+                return self.hide();
+            }
+            _ => {}
+        }
+
+        self.select.setup(ctx, SelectData::from(completions));
+        // the plugin (or the path completer) already picked what fits, so nothing is filtered out
+        self.select.set_filtering(false);
+
+        // the select's input field goes at the top, so its center is moved down from the input's
+        let center_y = input.config.y_position - input.background_rect.h / 2.0
+            + self.select.background_rect.h / 2.0;
+        self.select
+            .set_center(Vec2::new(input.config.x_position, center_y));
+
+        let select_input = &mut self.select.input_text_handler;
+        select_input.placeholder_text = input.placeholder_text.clone();
+        select_input.set_text(ctx, &input.entire_text);
+        self.select.background_rect.w = select_input.background_rect.w;
+        self.select.background_rect.x = select_input.background_rect.x;
+
+        self.shown = true;
+    }
+
+    pub fn hide(&mut self) {
+        self.shown = false;
+    }
+
+    // Enter replaces what's typed in input with the selected completion while they're shown.
+    // Returns whether enter was used
+    pub fn handle_enter(&mut self, ctx: &Context, input: &mut InputTextHandler) -> bool {
+        if !self.shown {
+            return false;
+        }
+
+        if let Some(completion) = self.select.selected_value() {
+            input.set_text(ctx, &completion);
+        }
+        self.hide();
+        true
+    }
+
+    // Up and Down go through the completions while they're shown, other keys hide them.
+    // Returns whether the key was used
+    pub fn handle_arrow_key(&mut self, ctx: &Context, key: Key) -> bool {
+        if self.shown && matches!(key, Key::Up | Key::Down) {
+            self.select.handle_arrow_key(ctx, key);
+            return true;
+        }
+
+        self.hide();
+        false
+    }
+
+    pub fn draw(&self, ctx: &Context, draw_list: &mut DrawList) {
+        self.select.draw(ctx, draw_list);
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.select.bounds()
+    }
+}
@@ -0,0 +1,588 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::FinishedMessage,
+    context::Context,
+    font::FontChain,
+    graphics::{Color, DrawList, Rect, Text, TextFragment, Vec2},
+    input::{Key, Shortcut},
+    screen::Screen,
+};
+
+use super::{
+    input_text::{InputTextConfig, InputTextHandler},
+    select::{SelectConfig, SelectHandler},
+    CanvasModeHandler,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub enum FormFieldKind {
+    Text,
+    Number,
+    Checkbox,
+    Dropdown(Vec<String>),
+    Secret,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct FormField {
+    pub id: String,
+    // if not given, the id is used as the label
+    #[serde(default)]
+    pub label: Option<String>,
+    pub kind: FormFieldKind,
+    #[serde(default)]
+    pub default: Option<serde_json::Value>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct FormData {
+    pub fields: Vec<FormField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormConfig {
+    #[serde(skip)]
+    pub input_text_config: InputTextConfig,
+    #[serde(skip)]
+    pub select_config: SelectConfig,
+    pub dropdown_options_shown: usize, // dropdowns show fewer options than a full select, to fit in the form
+    pub label_font_size: f32,
+    pub label_width: f32,
+    pub label_spacing: f32, // space in between a label and its field
+    pub row_spacing: f32,
+    pub padding: f32,
+    pub checkbox_size: f32,
+    #[serde(with = "crate::theme::color")]
+    pub text_color: Color,
+    #[serde(with = "crate::theme::color")]
+    pub error_color: Color,
+    pub background_outline_width: f32,
+    #[serde(with = "crate::theme::color")]
+    pub background_outline_color: Color,
+    #[serde(with = "crate::theme::color")]
+    pub background_color: Color,
+    pub focus_outline_width: f32,
+    #[serde(with = "crate::theme::color")]
+    pub focus_outline_color: Color,
+    #[serde(skip)]
+    pub x_position: f32,
+    #[serde(skip)]
+    pub y_position: f32,
+    #[serde(skip)]
+    pub font: FontChain, // used for labels and errors
+}
+
+impl Default for FormConfig {
+    fn default() -> Self {
+        Self {
+            input_text_config: Default::default(),
+            select_config: Default::default(),
+            dropdown_options_shown: 3,
+            label_font_size: 32.0,
+            label_width: 300.0,
+            label_spacing: 20.0,
+            row_spacing: 20.0,
+            padding: 20.0,
+            checkbox_size: 40.0,
+            text_color: Color::BLACK,
+            error_color: Color::from_rgb(200, 0, 0),
+            background_outline_width: 2.0,
+            background_outline_color: Color::BLACK,
+            background_color: Color::WHITE,
+            focus_outline_width: 4.0,
+            focus_outline_color: Color::from_rgb(80, 140, 255),
+            x_position: Screen::default().center_x(),
+            y_position: Screen::default().center_y(),
+            font: Default::default(),
+        }
+    }
+}
+
+pub enum FormWidget {
+    Input(Box<InputTextHandler>),
+    Dropdown(Box<SelectHandler>),
+    Checkbox { checked: bool, rect: Rect },
+}
+
+impl FormWidget {
+    fn bounds(&self) -> Rect {
+        match self {
+            FormWidget::Input(handler) => handler.bounds(),
+            FormWidget::Dropdown(handler) => handler.bounds(),
+            FormWidget::Checkbox { rect, .. } => *rect,
+        }
+    }
+
+    fn set_center(&mut self, center: Vec2) {
+        match self {
+            FormWidget::Input(handler) => handler.set_center(center),
+            FormWidget::Dropdown(handler) => handler.set_center(center),
+            FormWidget::Checkbox { rect, .. } => {
+                rect.x = center.x - rect.w / 2.0;
+                rect.y = center.y - rect.h / 2.0;
+            }
+        }
+    }
+}
+
+pub struct FormFieldState {
+    pub field: FormField,
+    pub label: Text,
+    pub label_position: Vec2,
+    pub widget: FormWidget,
+}
+
+pub struct FormHandler {
+    pub config: FormConfig,
+    pub finished_sender: Sender<FinishedMessage>,
+    // the fields' widgets never get enter forwarded to them, but they still need somewhere to send to
+    pub fields_finished_sender: Sender<FinishedMessage>,
+    pub fields_finished_receiver: Receiver<FinishedMessage>,
+    pub background_rect: Rect,
+    pub fields: Vec<FormFieldState>,
+    pub focused_field: Option<usize>,
+    pub error_text: Option<Text>,
+    pub finished: bool,
+}
+
+impl FormHandler {
+    pub fn get_outline_rect(&self) -> Rect {
+        let mut outline_rect = self.background_rect;
+        outline_rect.x -= self.config.background_outline_width;
+        outline_rect.y -= self.config.background_outline_width;
+        outline_rect.w += 2.0 * self.config.background_outline_width;
+        outline_rect.h += 2.0 * self.config.background_outline_width;
+
+        outline_rect
+    }
+
+    fn create_widget(&self, ctx: &Context, field: &FormField) -> FormWidget {
+        let mut input_config = self.config.input_text_config.clone();
+        // fields are left aligned next to their labels, so they can't grow sideways
+        input_config.text_max_width = input_config.text_min_width;
+
+        match &field.kind {
+            FormFieldKind::Text | FormFieldKind::Number | FormFieldKind::Secret => {
+                match field.kind {
+                    FormFieldKind::Number => {
+                        input_config.text_default_placeholder = String::from("0")
+                    }
+                    FormFieldKind::Secret => input_config.text_masked = true,
+                    _ => {}
+                }
+
+                let mut handler =
+                    InputTextHandler::new(ctx, &input_config, self.fields_finished_sender.clone());
+                handler.setup(ctx, ());
+
+                match &field.default {
+                    Some(serde_json::Value::String(text)) => handler.set_text(ctx, text),
+                    Some(serde_json::Value::Number(number)) => {
+                        handler.set_text(ctx, &number.to_string())
+                    }
+                    _ => {}
+                }
+
+                FormWidget::Input(Box::new(handler))
+            }
+
+            FormFieldKind::Dropdown(options) => {
+                let mut select_config = self.config.select_config.clone();
+                select_config.max_options_shown = self.config.dropdown_options_shown;
+                select_config.input_text_config.text_max_width =
+                    select_config.input_text_config.text_min_width;
+
+                let mut handler =
+                    SelectHandler::new(ctx, &select_config, self.fields_finished_sender.clone());
+                handler.setup(ctx, options.clone().into());
+
+                if let Some(serde_json::Value::String(option)) = &field.default {
+                    handler.select_value(option);
+                }
+
+                FormWidget::Dropdown(Box::new(handler))
+            }
+
+            FormFieldKind::Checkbox => FormWidget::Checkbox {
+                checked: field.default == Some(serde_json::Value::Bool(true)),
+                rect: Rect::new(
+                    0.0,
+                    0.0,
+                    self.config.checkbox_size,
+                    self.config.checkbox_size,
+                ),
+            },
+        }
+    }
+
+    fn make_text(&self, text: String, color: Color) -> Text {
+        self.config.font.text(TextFragment {
+            text,
+            color: Some(color),
+            scale: Some(self.config.label_font_size),
+            ..Default::default()
+        })
+    }
+
+    fn label_of(field: &FormField) -> &str {
+        field.label.as_deref().unwrap_or(&field.id)
+    }
+
+    // Gets the value of a single field, or an error message if it's not valid
+    fn field_value(state: &FormFieldState) -> Result<serde_json::Value, String> {
+        let label = Self::label_of(&state.field);
+        let required = state.field.required;
+
+        match &state.widget {
+            FormWidget::Input(handler) => {
+                let text = handler.entire_text.clone();
+
+                if text.trim().is_empty() {
+                    return if required {
+                        Err(format!("{} is required", label))
+                    } else if state.field.kind == FormFieldKind::Number {
+                        Ok(serde_json::Value::Null)
+                    } else {
+                        Ok(text.into())
+                    };
+                }
+
+                if state.field.kind != FormFieldKind::Number {
+                    return Ok(text.into());
+                }
+
+                let text = text.trim();
+                if let Ok(number) = text.parse::<i64>() {
+                    return Ok(number.into());
+                }
+
+                text.parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(serde_json::Value::Number)
+                    .ok_or(format!("{} must be a number", label))
+            }
+
+            FormWidget::Dropdown(handler) => match handler.selected_value() {
+                Some(option) => Ok(option.into()),
+                None if required => Err(format!("{} is required", label)),
+                None => Ok(serde_json::Value::Null),
+            },
+
+            FormWidget::Checkbox { checked, .. } => {
+                if required && !checked {
+                    Err(format!("{} must be checked", label))
+                } else {
+                    Ok((*checked).into())
+                }
+            }
+        }
+    }
+
+    // Validates every field, and either finishes with their values or shows the first error
+    fn submit(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        let mut values = serde_json::Map::new();
+
+        for i in 0..self.fields.len() {
+            match Self::field_value(&self.fields[i]) {
+                Ok(value) => {
+                    values.insert(self.fields[i].field.id.clone(), value);
+                }
+
+                Err(error) => {
+                    self.error_text = Some(self.make_text(error, self.config.error_color));
+                    self.focused_field = Some(i);
+                    return;
+                }
+            }
+        }
+
+        self.finished = true;
+        self.finished_sender
+            .send(FinishedMessage::UserInput(values.into()))
+            .unwrap();
+    }
+}
+
+impl CanvasModeHandler for FormHandler {
+    type ConfigData = FormConfig;
+    type SetupData = FormData;
+
+    fn new(
+        _ctx: &Context,
+        config: &Self::ConfigData,
+        finished_sender: Sender<FinishedMessage>,
+    ) -> Self {
+        let (fields_finished_sender, fields_finished_receiver) = channel();
+
+        Self {
+            config: config.clone(),
+            finished_sender,
+            fields_finished_sender,
+            fields_finished_receiver,
+            background_rect: Rect::new(config.x_position, config.y_position, 0.0, 0.0),
+            fields: vec![],
+            focused_field: None,
+            error_text: None,
+            finished: false,
+        }
+    }
+
+    fn setup(&mut self, ctx: &Context, data: Self::SetupData) {
+        self.finished = false;
+        self.error_text = None;
+        self.fields.clear();
+
+        for field in data.fields {
+            let widget = self.create_widget(ctx, &field);
+            let label = self.make_text(Self::label_of(&field).to_string(), self.config.text_color);
+
+            self.fields.push(FormFieldState {
+                field,
+                label,
+                label_position: Vec2::ZERO,
+                widget,
+            });
+        }
+
+        // every row is as tall as the taller of its label and its widget
+        let row_heights = self
+            .fields
+            .iter()
+            .map(|f| {
+                let label_height = ctx.measure(&f.label).h;
+                label_height.max(f.widget.bounds().h)
+            })
+            .collect::<Vec<_>>();
+
+        let widgets_width = self
+            .fields
+            .iter()
+            .map(|f| f.widget.bounds().w)
+            .fold(0.0, f32::max);
+
+        // the last row is left empty for validation errors
+        let error_row_height = self.config.label_font_size;
+
+        let width = 2.0 * self.config.padding
+            + self.config.label_width
+            + self.config.label_spacing
+            + widgets_width;
+        let height = 2.0 * self.config.padding
+            + row_heights.iter().sum::<f32>()
+            + self.config.row_spacing * row_heights.len() as f32
+            + error_row_height;
+
+        self.background_rect = Rect::new(
+            self.config.x_position - width / 2.0,
+            self.config.y_position - height / 2.0,
+            width,
+            height,
+        );
+
+        let label_x = self.background_rect.x + self.config.padding;
+        let widget_x = label_x + self.config.label_width + self.config.label_spacing;
+        let mut y = self.background_rect.y + self.config.padding;
+
+        for (state, row_height) in self.fields.iter_mut().zip(row_heights) {
+            let row_center = y + row_height / 2.0;
+
+            let label_height = ctx.measure(&state.label).h;
+            state.label_position = Vec2::new(label_x, row_center - label_height / 2.0);
+
+            let widget_width = state.widget.bounds().w;
+            state
+                .widget
+                .set_center(Vec2::new(widget_x + widget_width / 2.0, row_center));
+
+            y += row_height + self.config.row_spacing;
+        }
+
+        self.focused_field = if self.fields.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+    }
+
+    fn draw(&self, ctx: &Context, draw_list: &mut DrawList) {
+        // draw form background outline
+        draw_list.rect(
+            &self.get_outline_rect(),
+            &self.config.background_outline_color,
+        );
+
+        // draw form background
+        draw_list.rect(&self.background_rect, &self.config.background_color);
+
+        for (i, state) in self.fields.iter().enumerate() {
+            draw_list.text(&state.label, state.label_position);
+
+            // draw an outline around the focused field
+            if Some(i) == self.focused_field {
+                let mut focus_rect = state.widget.bounds();
+                focus_rect.x -= self.config.focus_outline_width;
+                focus_rect.y -= self.config.focus_outline_width;
+                focus_rect.w += 2.0 * self.config.focus_outline_width;
+                focus_rect.h += 2.0 * self.config.focus_outline_width;
+
+                draw_list.rect(&focus_rect, &self.config.focus_outline_color);
+            }
+
+            match &state.widget {
+                FormWidget::Input(handler) => handler.draw(ctx, draw_list),
+
+                FormWidget::Dropdown(handler) => handler.draw(ctx, draw_list),
+
+                FormWidget::Checkbox { checked, rect } => {
+                    draw_list.rect(rect, &self.config.background_outline_color);
+
+                    let outline = self.config.background_outline_width;
+                    let inner_rect = Rect::new(
+                        rect.x + outline,
+                        rect.y + outline,
+                        rect.w - 2.0 * outline,
+                        rect.h - 2.0 * outline,
+                    );
+                    draw_list.rect(&inner_rect, &self.config.background_color);
+
+                    if *checked {
+                        let check_rect = Rect::new(
+                            rect.x + rect.w / 4.0,
+                            rect.y + rect.h / 4.0,
+                            rect.w / 2.0,
+                            rect.h / 2.0,
+                        );
+                        draw_list.rect(&check_rect, &self.config.text_color);
+                    }
+                }
+            }
+        }
+
+        // draw validation error in the bottom row
+        if let Some(error_text) = &self.error_text {
+            draw_list.text(
+                error_text,
+                Vec2::new(
+                    self.background_rect.x + self.config.padding,
+                    self.background_rect.bottom()
+                        - self.config.padding
+                        - self.config.label_font_size,
+                ),
+            );
+        }
+    }
+
+    fn handle_text_input(&mut self, ctx: &Context, inputted_char: char) {
+        let focused_field = match self.focused_field {
+            Some(i) => i,
+            None => return,
+        };
+
+        match &mut self.fields[focused_field].widget {
+            FormWidget::Input(handler) => handler.handle_text_input(ctx, inputted_char),
+
+            FormWidget::Dropdown(handler) => handler.handle_text_input(ctx, inputted_char),
+
+            FormWidget::Checkbox { checked, .. } => {
+                if inputted_char == ' ' {
+                    *checked = !*checked;
+                }
+            }
+        }
+    }
+
+    fn handle_backspace(&mut self, ctx: &Context) {
+        let focused_field = match self.focused_field {
+            Some(i) => i,
+            None => return,
+        };
+
+        match &mut self.fields[focused_field].widget {
+            FormWidget::Input(handler) => handler.handle_backspace(ctx),
+
+            FormWidget::Dropdown(handler) => handler.handle_backspace(ctx),
+
+            FormWidget::Checkbox { .. } => {}
+        }
+    }
+
+    // enter moves on to the next field, and submits the form on the last one
+    fn handle_enter(&mut self, _ctx: &Context) {
+        match self.focused_field {
+            Some(i) if i + 1 < self.fields.len() => self.focused_field = Some(i + 1),
+            _ => self.submit(),
+        }
+    }
+
+    fn handle_arrow_key(&mut self, ctx: &Context, key: Key) {
+        let focused_field = match self.focused_field {
+            Some(i) => i,
+            None => return,
+        };
+
+        match &mut self.fields[focused_field].widget {
+            FormWidget::Input(handler) => handler.handle_arrow_key(ctx, key),
+
+            FormWidget::Dropdown(handler) => handler.handle_arrow_key(ctx, key),
+
+            FormWidget::Checkbox { .. } => {}
+        }
+    }
+
+    fn handle_shortcut(&mut self, ctx: &Context, shortcut: Shortcut) {
+        if let Some(i) = self.focused_field {
+            if let FormWidget::Input(handler) = &mut self.fields[i].widget {
+                handler.handle_shortcut(ctx, shortcut);
+            }
+        }
+    }
+
+    // Tab goes through the fields, wrapping around after the last one (which a layout
+    // it's in takes as going to its next child instead)
+    fn handle_tab(&mut self, _ctx: &Context) -> bool {
+        match self.focused_field {
+            Some(i) => {
+                self.focused_field = Some((i + 1) % self.fields.len());
+                i + 1 < self.fields.len()
+            }
+            None => false,
+        }
+    }
+
+    fn bounds(&self) -> Rect {
+        self.get_outline_rect()
+    }
+
+    fn set_center(&mut self, center: Vec2) {
+        let offset = center
+            - Vec2::new(
+                self.background_rect.x + self.background_rect.w / 2.0,
+                self.background_rect.y + self.background_rect.h / 2.0,
+            );
+
+        self.config.x_position = center.x;
+        self.config.y_position = center.y;
+        self.background_rect.x += offset.x;
+        self.background_rect.y += offset.y;
+
+        for state in &mut self.fields {
+            state.label_position += offset;
+
+            let bounds = state.widget.bounds();
+            let widget_center = Vec2::new(bounds.x + bounds.w / 2.0, bounds.y + bounds.h / 2.0);
+            state.widget.set_center(widget_center + offset);
+        }
+    }
+}
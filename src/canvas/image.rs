@@ -0,0 +1,322 @@
+use std::{rc::Rc, sync::mpsc::Sender, time};
+
+use base64::{engine::general_purpose, Engine};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::FinishedMessage,
+    clock::Clock,
+    context::Context,
+    error::Error,
+    font::FontChain,
+    graphics::{Color, DrawList, Image, Rect, Text, TextFragment, Vec2},
+    input::Key,
+    screen::Screen,
+};
+
+use super::CanvasModeHandler;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(default)]
+pub struct ImageData {
+    // exactly one of path and base64 should be given (if both are, path wins)
+    pub path: Option<String>,
+    pub base64: Option<String>,
+    // the box the image is scaled down to fit in (in 1080p pixels, like theme sizes),
+    // if not given the config's max size is used
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+    pub caption: Option<String>,
+    // if given, the image closes on its own after this many milliseconds.
+    // Deprecated, the message's timeout_ms does the same (see schema::deprecations)
+    #[deprecated]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageConfig {
+    pub max_width: f32,
+    pub max_height: f32,
+    pub padding: f32,
+    pub caption_font_size: f32,
+    pub caption_spacing: f32,
+    #[serde(with = "crate::theme::color")]
+    pub text_color: Color,
+    pub background_outline_width: f32,
+    #[serde(with = "crate::theme::color")]
+    pub background_outline_color: Color,
+    #[serde(with = "crate::theme::color")]
+    pub background_color: Color,
+    #[serde(skip)]
+    pub y_position: f32,
+    #[serde(skip)]
+    pub x_position: f32,
+    #[serde(skip)]
+    pub font: FontChain, // used for the caption
+}
+
+impl Default for ImageConfig {
+    fn default() -> Self {
+        Self {
+            max_width: 1200.0,
+            max_height: 700.0,
+            padding: 10.0,
+            caption_font_size: 32.0,
+            caption_spacing: 10.0,
+            text_color: Color::BLACK,
+            background_outline_width: 2.0,
+            background_outline_color: Color::BLACK,
+            background_color: Color::WHITE,
+            y_position: Screen::default().center_y(),
+            x_position: Screen::default().center_x(),
+            font: Default::default(),
+        }
+    }
+}
+
+pub struct ImageHandler {
+    pub config: ImageConfig,
+    pub finished_sender: Sender<FinishedMessage>,
+    pub background_rect: Rect,
+    pub image: Option<Image>,
+    pub image_scale: f32,
+    pub caption: Option<Text>,
+    pub timeout: Option<time::Duration>,
+    pub time_shown: time::Duration,
+    pub finished: bool,
+    pub clock: Rc<dyn Clock>,
+}
+
+impl ImageHandler {
+    pub fn get_outline_rect(&self) -> Rect {
+        let mut outline_rect = self.background_rect;
+        outline_rect.x -= self.config.background_outline_width;
+        outline_rect.y -= self.config.background_outline_width;
+        outline_rect.w += 2.0 * self.config.background_outline_width;
+        outline_rect.h += 2.0 * self.config.background_outline_width;
+
+        outline_rect
+    }
+
+    // Reads the encoded image bytes either from the given path or from the base64 string
+    fn load_bytes(data: &ImageData) -> Result<Vec<u8>, String> {
+        if let Some(path) = &data.path {
+            return std::fs::read(path).map_err(|e| format!("Could not read {}: {}", path, e));
+        }
+
+        if let Some(encoded) = &data.base64 {
+            // also accept data urls like "data:image/png;base64,...."
+            let encoded = match encoded.strip_prefix("data:") {
+                Some(rest) => rest.split_once(',').map(|(_, e)| e).unwrap_or(rest),
+                None => encoded,
+            };
+
+            return general_purpose::STANDARD
+                .decode(encoded.trim())
+                .map_err(|e| format!("Invalid base64 image: {}", e));
+        }
+
+        Err(String::from("No image path or base64 data given"))
+    }
+
+    fn finish(&mut self, data: String) {
+        if self.finished {
+            return;
+        }
+
+        self.finished = true;
+        self.finished_sender
+            .send(FinishedMessage::UserInput(data.into()))
+            .unwrap();
+    }
+
+    fn fail(&mut self, error: Error) {
+        if self.finished {
+            return;
+        }
+
+        self.finished = true;
+        self.finished_sender
+            .send(FinishedMessage::Failed(error))
+            .unwrap();
+    }
+}
+
+impl CanvasModeHandler for ImageHandler {
+    type ConfigData = ImageConfig;
+    type SetupData = ImageData;
+
+    fn new(
+        ctx: &Context,
+        config: &Self::ConfigData,
+        finished_sender: Sender<FinishedMessage>,
+    ) -> Self {
+        Self {
+            config: config.clone(),
+            finished_sender,
+            background_rect: Rect::new(config.x_position, config.y_position, 0.0, 0.0),
+            image: None,
+            image_scale: 1.0,
+            caption: None,
+            timeout: None,
+            time_shown: ctx.clock().time_since_start(),
+            finished: false,
+            clock: ctx.clock(),
+        }
+    }
+
+    fn setup(&mut self, ctx: &Context, data: Self::SetupData) {
+        self.finished = false;
+        self.image = None;
+        self.caption = None;
+        self.time_shown = self.clock.time_since_start();
+        #[allow(deprecated)]
+        let timeout_ms = data.timeout_ms;
+        self.timeout = timeout_ms.map(time::Duration::from_millis);
+
+        let image = match Self::load_bytes(&data) {
+            Ok(bytes) => Image::from_bytes(&bytes),
+            Err(e) => Err(e),
+        };
+
+        let image = match image {
+            Ok(image) => image,
+            Err(e) => {
+                // nothing to show, so fail straight away and let the plugin know why
+                self.fail(Error::Image(e));
+                return;
+            }
+        };
+
+        // scale the image down (never up) so it fits inside the max box, keeping its aspect ratio
+        let max_width = data
+            .max_width
+            .map(|w| w as f32 * ctx.screen().scale)
+            .unwrap_or(self.config.max_width);
+        let max_height = data
+            .max_height
+            .map(|h| h as f32 * ctx.screen().scale)
+            .unwrap_or(self.config.max_height);
+
+        let image_width = image.width() as f32;
+        let image_height = image.height() as f32;
+        self.image_scale = (max_width / image_width)
+            .min(max_height / image_height)
+            .min(1.0);
+
+        let mut content_width = image_width * self.image_scale;
+        let mut content_height = image_height * self.image_scale;
+
+        if let Some(caption) = data.caption {
+            let mut text = self.config.font.text(TextFragment {
+                text: caption,
+                color: Some(self.config.text_color),
+                scale: Some(self.config.caption_font_size),
+                ..Default::default()
+            });
+            text.set_bounds(Vec2::new(max_width, f32::MAX));
+
+            let caption_rect = ctx.measure(&text);
+            content_width = content_width.max(caption_rect.w);
+            content_height += self.config.caption_spacing + caption_rect.h;
+
+            self.caption = Some(text);
+        }
+
+        let width = content_width + 2.0 * self.config.padding;
+        let height = content_height + 2.0 * self.config.padding;
+        self.background_rect = Rect::new(
+            self.config.x_position - width / 2.0,
+            self.config.y_position - height / 2.0,
+            width,
+            height,
+        );
+
+        self.image = Some(image);
+    }
+
+    fn update(&mut self, _ctx: &Context) {
+        if let Some(timeout) = self.timeout {
+            let elapsed = self.clock.time_since_start() - self.time_shown;
+
+            if elapsed >= timeout {
+                self.finish(String::from("Timed out"));
+            }
+        }
+    }
+
+    fn draw(&self, ctx: &Context, draw_list: &mut DrawList) {
+        let image = match &self.image {
+            Some(image) => image,
+            None => return,
+        };
+
+        // draw background outline
+        draw_list.rect(
+            &self.get_outline_rect(),
+            &self.config.background_outline_color,
+        );
+
+        // draw background
+        draw_list.rect(&self.background_rect, &self.config.background_color);
+
+        // draw image, horizontally centered
+        let image_width = image.width() as f32 * self.image_scale;
+        let image_height = image.height() as f32 * self.image_scale;
+        let image_y = self.background_rect.y + self.config.padding;
+
+        draw_list.image(
+            image,
+            Vec2::new(self.config.x_position - image_width / 2.0, image_y),
+            Vec2::new(self.image_scale, self.image_scale),
+        );
+
+        // draw caption under the image, also horizontally centered
+        if let Some(caption) = &self.caption {
+            let caption_rect = ctx.measure(caption);
+
+            draw_list.text(
+                caption,
+                Vec2::new(
+                    self.config.x_position - caption_rect.w / 2.0,
+                    image_y + image_height + self.config.caption_spacing,
+                ),
+            );
+        }
+    }
+
+    fn bounds(&self) -> Rect {
+        self.get_outline_rect()
+    }
+
+    fn set_center(&mut self, center: Vec2) {
+        self.config.x_position = center.x;
+        self.config.y_position = center.y;
+
+        self.background_rect.x = center.x - self.background_rect.w / 2.0;
+        self.background_rect.y = center.y - self.background_rect.h / 2.0;
+    }
+
+    // any key closes the image
+
+    fn handle_text_input(&mut self, _ctx: &Context, inputted_char: char) {
+        // enter and backspace also come through here as control chars, but they're handled separately
+        if !inputted_char.is_control() {
+            self.finish(String::from("Closed"));
+        }
+    }
+
+    fn handle_backspace(&mut self, _ctx: &Context) {
+        self.finish(String::from("Closed"));
+    }
+
+    fn handle_enter(&mut self, _ctx: &Context) {
+        self.finish(String::from("Closed"));
+    }
+
+    fn handle_arrow_key(&mut self, _ctx: &Context, _key: Key) {
+        self.finish(String::from("Closed"));
+    }
+}
@@ -1,29 +1,46 @@
-use std::{str::from_utf8, sync::mpsc::Sender};
+use std::sync::mpsc::Sender;
 
-use ggez::{
-    glam::Vec2,
-    graphics::{Color, DrawParam, Drawable, PxScale, Rect, Text, TextFragment},
-};
+use serde::{Deserialize, Serialize};
 
-use crate::{app::FinishedMessage, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::{
+    app::FinishedMessage,
+    context::Context,
+    font::FontChain,
+    graphics::{Color, DrawList, Rect, Text, TextFragment, Vec2},
+    input::{Key, Shortcut},
+    screen::Screen,
+};
 
 use super::CanvasModeHandler;
 
-#[derive(Clone)]
+// the line shown above the field while searching the history is smaller than the inputted text
+const SEARCH_FONT_SCALE: f32 = 0.6;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct InputTextConfig {
     pub text_min_width: f32,
     pub text_max_width: f32,
     pub text_font_size: f32,
     pub text_horizontal_padding: f32,
     pub text_vertical_padding: f32,
-    pub text_default_placeholder: &'static str,
+    pub text_default_placeholder: String,
     pub text_placeholder_alpha: f32, // from 0 to 1
+    #[serde(with = "crate::theme::color")]
     pub text_color: Color,
+    #[serde(skip)]
+    pub text_masked: bool, // shows every character as * (for passwords)
     pub background_outline_width: f32,
+    #[serde(with = "crate::theme::color")]
     pub background_outline_color: Color,
+    #[serde(with = "crate::theme::color")]
     pub background_color: Color,
+    #[serde(skip)]
     pub y_position: f32,
+    #[serde(skip)]
     pub x_position: f32,
+    #[serde(skip)]
+    pub font: FontChain,
 }
 
 impl Default for InputTextConfig {
@@ -34,14 +51,16 @@ impl Default for InputTextConfig {
             text_font_size: 32.0,
             text_horizontal_padding: 10.0,
             text_vertical_padding: 15.0,
-            text_default_placeholder: "Enter text...",
+            text_default_placeholder: String::from("Enter text..."),
             text_placeholder_alpha: 0.7,
             text_color: Color::BLACK,
+            text_masked: false,
             background_outline_width: 2.0,
             background_outline_color: Color::BLACK,
             background_color: Color::WHITE,
-            y_position: SCREEN_HEIGHT / 2.0,
-            x_position: SCREEN_WIDTH / 2.0,
+            y_position: Screen::default().center_y(),
+            x_position: Screen::default().center_x(),
+            font: Default::default(),
         }
     }
 }
@@ -53,6 +72,21 @@ pub struct InputTextHandler {
     pub placeholder_text: Text,
     pub displayed_text: Text,
     pub entire_text: String,
+    // what was submitted before (oldest first), which Up, Down and Ctrl+R go through
+    pub history: Vec<String>,
+    // the history entry that's shown, None while it's what the user typed
+    history_position: Option<usize>,
+    // what the user typed, kept while a history entry is shown
+    draft: String,
+    // set while Ctrl+R is searching the history
+    search: Option<HistorySearch>,
+}
+
+// An incremental reverse search: typing adds to the query, and every Ctrl+R finds an older match
+#[derive(Debug, Default)]
+struct HistorySearch {
+    query: String,
+    found: Option<usize>,
 }
 
 impl InputTextHandler {
@@ -66,6 +100,159 @@ impl InputTextHandler {
         outline_rect
     }
 
+    // Replaces the text with *s if the input is masked, keeping the length the same
+    fn mask(&self, text: String) -> String {
+        if self.config.text_masked {
+            "*".repeat(text.chars().count())
+        } else {
+            text
+        }
+    }
+
+    // Every inputted char is its own fragment, so removing the last one removes the last char
+    fn char_fragment(&self, ch: char) -> TextFragment {
+        self.config.font.fragment(TextFragment {
+            text: self.mask(ch.to_string()),
+            color: Some(self.config.text_color),
+            scale: Some(self.config.text_font_size),
+            ..Default::default()
+        })
+    }
+
+    // Text made of only the last count inputted chars (used when the text doesn't fit)
+    fn text_of_last_chars(&self, count: usize) -> Text {
+        let skipped = self.entire_text.chars().count().saturating_sub(count);
+
+        let mut text = Text::new("");
+        for ch in self.entire_text.chars().skip(skipped) {
+            text.add(self.char_fragment(ch));
+        }
+
+        text
+    }
+
+    // Replaces the inputted text, as if the user typed it in
+    pub fn set_text(&mut self, ctx: &Context, text: &str) {
+        self.background_rect.w = self.config.text_min_width;
+        self.background_rect.x = self.config.x_position - self.config.text_min_width / 2.0;
+        self.displayed_text = Text::new("");
+        self.entire_text = String::new();
+
+        for ch in text.chars().filter(|&ch| Self::accepts(ch)) {
+            self.insert_char(ctx, ch);
+        }
+    }
+
+    // Replaces the history, going back to what the user typed
+    pub fn set_history(&mut self, history: Vec<String>) {
+        self.history = history;
+        self.history_position = None;
+        self.search = None;
+    }
+
+    // Shows the history entry at position, or what the user typed if it's None
+    fn show_history_entry(&mut self, ctx: &Context, position: Option<usize>) {
+        if self.history_position.is_none() {
+            self.draft = self.entire_text.clone();
+        }
+        self.history_position = position;
+
+        let text = match position {
+            Some(i) => self.history[i].clone(),
+            None => self.draft.clone(),
+        };
+        self.set_text(ctx, &text);
+    }
+
+    fn history_back(&mut self, ctx: &Context) {
+        let position = match self.history_position {
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+            Some(i) => i.saturating_sub(1),
+        };
+
+        self.show_history_entry(ctx, Some(position));
+    }
+
+    fn history_forward(&mut self, ctx: &Context) {
+        let position = match self.history_position {
+            None => return,
+            Some(i) if i + 1 < self.history.len() => Some(i + 1),
+            Some(_) => None,
+        };
+
+        self.show_history_entry(ctx, position);
+    }
+
+    // Finds the newest entry before the given position that has the query in it (ignoring case),
+    // and shows it if there is one
+    fn search_history(&mut self, ctx: &Context, before: usize) {
+        let search = match &self.search {
+            Some(search) => search,
+            None => return,
+        };
+
+        let query = search.query.to_lowercase();
+        if query.is_empty() {
+            return;
+        }
+
+        let found = (0..before.min(self.history.len()))
+            .rev()
+            .find(|&i| self.history[i].to_lowercase().contains(&query));
+
+        if let Some(i) = found {
+            self.search.as_mut().unwrap().found = Some(i);
+            self.show_history_entry(ctx, Some(i));
+        } else {
+            self.search.as_mut().unwrap().found = None;
+        }
+    }
+
+    // The chars that can be typed in, others (like control characters) are ignored
+    fn accepts(ch: char) -> bool {
+        ch.is_alphanumeric() || ch.is_ascii_punctuation() || ch == ' '
+    }
+
+    fn insert_char(&mut self, ctx: &Context, inputted_char: char) {
+        // add inputted char to text
+        self.entire_text.push(inputted_char);
+        self.displayed_text.add(self.char_fragment(inputted_char));
+
+        // handle text overflowing the input field
+        let text_rect = ctx.measure(&self.displayed_text);
+
+        if text_rect.w + (2.0 * self.config.text_horizontal_padding) >= self.background_rect.w {
+            let new_width = text_rect.w + (2.0 * self.config.text_horizontal_padding);
+
+            if new_width <= self.config.text_max_width {
+                // just expand the input field if there's still space left
+                self.background_rect.w = new_width;
+                self.background_rect.x = self.config.x_position - new_width / 2.0;
+            } else {
+                // if the input field is already maximally expanded, then scroll the text so its end is visible
+                let shown = self.displayed_text.contents().chars().count() - 1;
+                self.displayed_text = self.text_of_last_chars(shown);
+            }
+        }
+    }
+
+    // The line above the input field while searching, like "search: que" (or "no match: quer")
+    fn search_text(&self, search: &HistorySearch) -> Text {
+        let prefix = if search.found.is_some() || search.query.is_empty() {
+            "search"
+        } else {
+            "no match"
+        };
+
+        self.config.font.text(TextFragment {
+            text: format!("{}: {}", prefix, search.query),
+            color: Some(self.config.text_color),
+            scale: Some(self.config.text_font_size * SEARCH_FONT_SCALE),
+            ..Default::default()
+        })
+    }
+
     // Get the initial background rect that would be created with the given config when you call self.new
     pub fn get_initial_background_rect(config: &InputTextConfig) -> Rect {
         let width = config.text_min_width;
@@ -85,7 +272,7 @@ impl CanvasModeHandler for InputTextHandler {
     type SetupData = ();
 
     fn new(
-        _ggez_ctx: &mut ggez::Context,
+        _ctx: &Context,
         config: &Self::ConfigData,
         finished_sender: Sender<FinishedMessage>,
     ) -> Self {
@@ -104,10 +291,10 @@ impl CanvasModeHandler for InputTextHandler {
         let mut placeholder_color = config.text_color;
         placeholder_color.a = config.text_placeholder_alpha;
 
-        let placeholder_text = Text::new(TextFragment {
-            text: config.text_default_placeholder.to_string(),
+        let placeholder_text = config.font.text(TextFragment {
+            text: config.text_default_placeholder.clone(),
             color: Some(placeholder_color),
-            scale: Some(PxScale::from(config.text_font_size)),
+            scale: Some(config.text_font_size),
             ..Default::default()
         });
 
@@ -118,10 +305,14 @@ impl CanvasModeHandler for InputTextHandler {
             placeholder_text,
             displayed_text: Text::new(""),
             entire_text: String::new(),
+            history: Vec::new(),
+            history_position: None,
+            draft: String::new(),
+            search: None,
         }
     }
 
-    fn setup(&mut self, _ggez_ctx: &mut ggez::Context, _data: Self::SetupData) {
+    fn setup(&mut self, _ctx: &Context, _data: Self::SetupData) {
         // reset background rect
         self.background_rect.w = self.config.text_min_width;
         self.background_rect.x = self.config.x_position - self.config.text_min_width / 2.0;
@@ -129,128 +320,77 @@ impl CanvasModeHandler for InputTextHandler {
         // reset text
         self.displayed_text = Text::new("");
         self.entire_text = String::new();
+
+        // the history is set again by whatever needs it
+        self.set_history(Vec::new());
     }
 
-    fn draw(
-        &self,
-        _ggez_ctx: &mut ggez::Context,
-        ggez_canvas: &mut ggez::graphics::Canvas,
-        canvas_ctx: &super::CanvasContext,
-    ) {
+    fn draw(&self, _ctx: &Context, draw_list: &mut DrawList) {
         // draw background outline
-        canvas_ctx.draw_rect(
-            ggez_canvas,
+        draw_list.rect(
             &self.get_outline_rect(),
             &self.config.background_outline_color,
         );
 
         // draw background
-        canvas_ctx.draw_rect(
-            ggez_canvas,
-            &self.background_rect,
-            &self.config.background_color,
-        );
+        draw_list.rect(&self.background_rect, &self.config.background_color);
 
         let text_x = self.background_rect.x + self.config.text_horizontal_padding;
         let text_y = self.background_rect.y + self.background_rect.h / 2.0
             - (self.config.text_font_size / 2.0);
 
-        if self.entire_text.len() == 0 {
+        if self.entire_text.is_empty() {
             // display placeholder if there's no inputted text
-            ggez_canvas.draw(
-                &self.placeholder_text,
-                DrawParam::new().dest(Vec2::new(text_x, text_y)),
-            );
+            draw_list.text(&self.placeholder_text, Vec2::new(text_x, text_y));
         } else {
             // display inputted text
-            ggez_canvas.draw(
-                &self.displayed_text,
-                DrawParam::new().dest(Vec2::new(text_x, text_y)),
-            );
+            draw_list.text(&self.displayed_text, Vec2::new(text_x, text_y));
+        }
+
+        if let Some(search) = &self.search {
+            let search_text = self.search_text(search);
+            let outline_rect = self.get_outline_rect();
+            let search_y = outline_rect.y
+                - self.config.text_vertical_padding
+                - self.config.text_font_size * SEARCH_FONT_SCALE;
+            draw_list.text(&search_text, Vec2::new(outline_rect.x, search_y));
         }
     }
 
-    fn handle_text_input(&mut self, ggez_ctx: &ggez::Context, inputted_char: char) {
-        if !(inputted_char.is_alphanumeric()
-            || inputted_char.is_ascii_punctuation()
-            || inputted_char == ' ')
-        {
+    fn handle_text_input(&mut self, ctx: &Context, inputted_char: char) {
+        if !Self::accepts(inputted_char) {
             return;
         }
 
-        // add inputted char to text
-        self.entire_text = format!("{}{}", self.entire_text, inputted_char);
-        self.displayed_text.add(TextFragment {
-            text: inputted_char.to_string(),
-            color: Some(Color::BLACK),
-            scale: Some(PxScale::from(self.config.text_font_size)),
-            ..Default::default()
-        });
-
-        // handle text overflowing the input field
-        let text_rect = self.displayed_text.dimensions(&ggez_ctx.gfx).unwrap();
-
-        if text_rect.w + (2.0 * self.config.text_horizontal_padding) >= self.background_rect.w {
-            let new_width = text_rect.w + (2.0 * self.config.text_horizontal_padding);
+        // while searching, typing goes to the query, starting from the entry that's found
+        if let Some(search) = &mut self.search {
+            search.query.push(inputted_char);
+            let before = search.found.map_or(self.history.len(), |i| i + 1);
+            return self.search_history(ctx, before);
+        }
 
-            if new_width <= self.config.text_max_width {
-                // just expand the input field if there's still space left
-                self.background_rect.w = new_width;
-                self.background_rect.x = self.config.x_position - new_width / 2.0;
-            } else {
-                // if the input field is already maximally expanded, then scroll the text so its end is visible
-                self.displayed_text = {
-                    let mut new_text = Text::new("");
-
-                    let start = self.entire_text.len() - self.displayed_text.contents().len() + 1;
-                    for i in start..self.entire_text.len() {
-                        let text = from_utf8(&[self.entire_text.as_bytes()[i]])
-                            .unwrap()
-                            .to_string();
-
-                        new_text.add(TextFragment {
-                            text,
-                            color: Some(Color::BLACK),
-                            scale: Some(PxScale::from(self.config.text_font_size)),
-                            ..Default::default()
-                        });
-                    }
+        self.insert_char(ctx, inputted_char);
+    }
 
-                    new_text
-                };
-            }
+    fn handle_backspace(&mut self, ctx: &Context) {
+        // a shorter query can match newer entries again
+        if let Some(search) = &mut self.search {
+            search.query.pop();
+            return self.search_history(ctx, self.history.len());
         }
-    }
 
-    fn handle_backspace(&mut self, ggez_ctx: &ggez::Context) {
         // remove last character
-        if self.entire_text.len() == 0 {
+        if self.entire_text.is_empty() {
             return;
         }
 
-        self.entire_text.remove(self.entire_text.len() - 1);
+        self.entire_text.pop();
         self.displayed_text = {
-            let old_displayed_len = self.displayed_text.contents().len();
+            let old_displayed_len = self.displayed_text.contents().chars().count();
 
-            if old_displayed_len - 1 < self.entire_text.len() {
+            if old_displayed_len - 1 < self.entire_text.chars().count() {
                 // if there's more text than what's being displayed, handle that properly
-                let mut new_text = Text::new("");
-
-                let start = self.entire_text.len() - old_displayed_len;
-                for i in start..self.entire_text.len() {
-                    let text = from_utf8(&[self.entire_text.as_bytes()[i]])
-                        .unwrap()
-                        .to_string();
-
-                    new_text.add(TextFragment {
-                        text,
-                        color: Some(Color::BLACK),
-                        scale: Some(PxScale::from(self.config.text_font_size)),
-                        ..Default::default()
-                    });
-                }
-
-                new_text
+                self.text_of_last_chars(old_displayed_len)
             } else {
                 let remaining_fragments = self.displayed_text.fragments()
                     [..(self.displayed_text.fragments().len() - 1)]
@@ -266,7 +406,7 @@ impl CanvasModeHandler for InputTextHandler {
         };
 
         // reduce input width (up to the minimum)
-        let text_rect = self.displayed_text.dimensions(&ggez_ctx.gfx).unwrap();
+        let text_rect = ctx.measure(&self.displayed_text);
         let new_width = text_rect.w + (2.0 * self.config.text_horizontal_padding);
         if new_width >= self.config.text_min_width {
             self.background_rect.w = new_width;
@@ -274,16 +414,53 @@ impl CanvasModeHandler for InputTextHandler {
         }
     }
 
-    fn handle_arrow_key(
-        &mut self,
-        _ggez_ctx: &ggez::Context,
-        _keycode: ggez::input::keyboard::KeyCode,
-    ) {
+    fn handle_arrow_key(&mut self, ctx: &Context, key: Key) {
+        // arrow keys stop searching, keeping what was found
+        self.search = None;
+
+        match key {
+            Key::Up => self.history_back(ctx),
+            Key::Down => self.history_forward(ctx),
+            _ => {}
+        }
+    }
+
+    // Ctrl+R starts searching the history, and again goes to the next older match
+    fn handle_shortcut(&mut self, ctx: &Context, shortcut: Shortcut) {
+        match shortcut {
+            Shortcut::SearchHistory => {
+                if self.history.is_empty() {
+                    return;
+                }
+
+                match &self.search {
+                    Some(search) => {
+                        let before = search.found.unwrap_or(self.history.len());
+                        self.search_history(ctx, before)
+                    }
+
+                    None => self.search = Some(HistorySearch::default()),
+                }
+            }
+        }
+    }
+
+    fn bounds(&self) -> Rect {
+        self.get_outline_rect()
+    }
+
+    fn set_center(&mut self, center: Vec2) {
+        self.config.x_position = center.x;
+        self.config.y_position = center.y;
+
+        self.background_rect.x = center.x - self.background_rect.w / 2.0;
+        self.background_rect.y = center.y - self.background_rect.h / 2.0;
     }
 
-    fn handle_enter(&mut self, _ggez_ctx: &ggez::Context) {
+    fn handle_enter(&mut self, _ctx: &Context) {
+        self.search = None;
         self.finished_sender
-            .send(FinishedMessage::UserInput(self.entire_text.clone()))
+            .send(FinishedMessage::UserInput(self.entire_text.clone().into()))
             .unwrap();
     }
 }
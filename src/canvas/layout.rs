@@ -0,0 +1,702 @@
+use std::{
+    collections::HashSet,
+    sync::mpsc::{channel, Receiver, Sender},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::FinishedMessage,
+    completion::Completion,
+    context::Context,
+    error::Error,
+    graphics::{Color, DrawList, Rect, Vec2},
+    input::{Key, Shortcut},
+    screen::Screen,
+};
+
+use super::{
+    completions::Completions,
+    form::{FormConfig, FormHandler},
+    image::{ImageConfig, ImageHandler},
+    input_text::{InputTextConfig, InputTextHandler},
+    progress::{ProgressConfig, ProgressHandler},
+    select::{SelectConfig, SelectHandler},
+    CanvasMode, CanvasModeHandler,
+};
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub enum LayoutDirection {
+    Row,
+    #[default]
+    Column,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct LayoutChild {
+    pub id: String,
+    pub mode: CanvasMode,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct LayoutData {
+    #[serde(default)]
+    pub direction: LayoutDirection,
+    // space in between children, if not given the config's spacing is used
+    #[serde(default)]
+    pub spacing: Option<u32>,
+    pub children: Vec<LayoutChild>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    #[serde(skip)]
+    pub input_text_config: InputTextConfig,
+    #[serde(skip)]
+    pub select_config: SelectConfig,
+    #[serde(skip)]
+    pub image_config: ImageConfig,
+    #[serde(skip)]
+    pub form_config: FormConfig,
+    #[serde(skip)]
+    pub progress_config: ProgressConfig,
+    pub spacing: f32,
+    pub focus_outline_width: f32,
+    #[serde(with = "crate::theme::color")]
+    pub focus_outline_color: Color,
+    #[serde(skip)]
+    pub x_position: f32,
+    #[serde(skip)]
+    pub y_position: f32,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            input_text_config: Default::default(),
+            select_config: Default::default(),
+            image_config: Default::default(),
+            form_config: Default::default(),
+            progress_config: Default::default(),
+            spacing: 30.0,
+            focus_outline_width: 4.0,
+            focus_outline_color: Color::from_rgb(80, 140, 255),
+            x_position: Screen::default().center_x(),
+            y_position: Screen::default().center_y(),
+        }
+    }
+}
+
+pub enum LayoutChildHandler {
+    InputText(InputTextHandler),
+    Select(Box<SelectHandler>),
+    Image(ImageHandler),
+    Layout(Box<LayoutHandler>),
+    Form(Box<FormHandler>),
+    Progress(ProgressHandler),
+}
+
+impl LayoutChildHandler {
+    // basically does what Canvas does, but for a single child
+    fn get(&self) -> &dyn LayoutChildDispatch {
+        match self {
+            LayoutChildHandler::InputText(h) => h,
+            LayoutChildHandler::Select(h) => h.as_ref(),
+            LayoutChildHandler::Image(h) => h,
+            LayoutChildHandler::Layout(h) => h.as_ref(),
+            LayoutChildHandler::Form(h) => h.as_ref(),
+            LayoutChildHandler::Progress(h) => h,
+        }
+    }
+
+    fn get_mut(&mut self) -> &mut dyn LayoutChildDispatch {
+        match self {
+            LayoutChildHandler::InputText(h) => h,
+            LayoutChildHandler::Select(h) => h.as_mut(),
+            LayoutChildHandler::Image(h) => h,
+            LayoutChildHandler::Layout(h) => h.as_mut(),
+            LayoutChildHandler::Form(h) => h.as_mut(),
+            LayoutChildHandler::Progress(h) => h,
+        }
+    }
+
+    // whether the child can receive keyboard focus
+    fn focusable(&self) -> bool {
+        match self {
+            LayoutChildHandler::InputText(_)
+            | LayoutChildHandler::Select(_)
+            | LayoutChildHandler::Form(_) => true,
+            LayoutChildHandler::Image(_) | LayoutChildHandler::Progress(_) => false,
+            LayoutChildHandler::Layout(h) => h.children.iter().any(|c| c.handler.focusable()),
+        }
+    }
+}
+
+// CanvasModeHandler isn't object safe (because of new and the associated types), so this
+// trait exposes just the parts a layout needs to forward to its children
+trait LayoutChildDispatch {
+    fn update(&mut self, ctx: &Context);
+    fn draw(&self, ctx: &Context, draw_list: &mut DrawList);
+    fn handle_text_input(&mut self, ctx: &Context, inputted_char: char);
+    fn handle_backspace(&mut self, ctx: &Context);
+    fn handle_enter(&mut self, ctx: &Context);
+    fn handle_arrow_key(&mut self, ctx: &Context, key: Key);
+    fn handle_shortcut(&mut self, ctx: &Context, shortcut: Shortcut);
+    fn handle_tab(&mut self, ctx: &Context) -> bool;
+    fn bounds(&self) -> Rect;
+    fn set_center(&mut self, center: Vec2);
+}
+
+impl<T: CanvasModeHandler> LayoutChildDispatch for T {
+    fn update(&mut self, ctx: &Context) {
+        CanvasModeHandler::update(self, ctx)
+    }
+
+    fn draw(&self, ctx: &Context, draw_list: &mut DrawList) {
+        CanvasModeHandler::draw(self, ctx, draw_list)
+    }
+
+    fn handle_text_input(&mut self, ctx: &Context, inputted_char: char) {
+        CanvasModeHandler::handle_text_input(self, ctx, inputted_char)
+    }
+
+    fn handle_backspace(&mut self, ctx: &Context) {
+        CanvasModeHandler::handle_backspace(self, ctx)
+    }
+
+    fn handle_enter(&mut self, ctx: &Context) {
+        CanvasModeHandler::handle_enter(self, ctx)
+    }
+
+    fn handle_arrow_key(&mut self, ctx: &Context, key: Key) {
+        CanvasModeHandler::handle_arrow_key(self, ctx, key)
+    }
+
+    fn handle_shortcut(&mut self, ctx: &Context, shortcut: Shortcut) {
+        CanvasModeHandler::handle_shortcut(self, ctx, shortcut)
+    }
+
+    fn handle_tab(&mut self, ctx: &Context) -> bool {
+        CanvasModeHandler::handle_tab(self, ctx)
+    }
+
+    fn bounds(&self) -> Rect {
+        CanvasModeHandler::bounds(self)
+    }
+
+    fn set_center(&mut self, center: Vec2) {
+        CanvasModeHandler::set_center(self, center)
+    }
+}
+
+pub struct LayoutChildState {
+    pub id: String,
+    pub handler: LayoutChildHandler,
+    // every child gets its own channel, so the layout knows which child finished
+    pub finished_receiver: Receiver<FinishedMessage>,
+    pub value: Option<serde_json::Value>,
+}
+
+pub struct LayoutHandler {
+    pub config: LayoutConfig,
+    pub finished_sender: Sender<FinishedMessage>,
+    pub children: Vec<LayoutChildState>,
+    pub focused_child: Option<usize>,
+    pub finished: bool,
+    // what Tab shows under the focused child, if it's an InputText
+    pub completions: Completions,
+}
+
+impl LayoutHandler {
+    fn create_child(
+        &self,
+        ctx: &Context,
+        direction: LayoutDirection,
+        mode: CanvasMode,
+        finished_sender: Sender<FinishedMessage>,
+    ) -> LayoutChildHandler {
+        match mode {
+            CanvasMode::InputText => {
+                let mut config = self.config.input_text_config.clone();
+                if direction == LayoutDirection::Row {
+                    // growing sideways would make it overlap its neighbours, so scroll instead
+                    config.text_max_width = config.text_min_width;
+                }
+
+                let mut handler = InputTextHandler::new(ctx, &config, finished_sender);
+                handler.setup(ctx, ());
+                LayoutChildHandler::InputText(handler)
+            }
+
+            CanvasMode::Select(data) => {
+                let mut config = self.config.select_config.clone();
+                if direction == LayoutDirection::Row {
+                    config.input_text_config.text_max_width =
+                        config.input_text_config.text_min_width;
+                }
+
+                let mut handler = SelectHandler::new(ctx, &config, finished_sender);
+                handler.setup(ctx, data);
+                LayoutChildHandler::Select(Box::new(handler))
+            }
+
+            CanvasMode::Image(data) => {
+                let mut handler =
+                    ImageHandler::new(ctx, &self.config.image_config, finished_sender);
+                handler.setup(ctx, data);
+                LayoutChildHandler::Image(handler)
+            }
+
+            CanvasMode::Layout(data) => {
+                let mut handler = LayoutHandler::new(ctx, &self.config, finished_sender);
+                handler.completions.completion = self.completions.completion;
+                handler.setup(ctx, data);
+                LayoutChildHandler::Layout(Box::new(handler))
+            }
+
+            CanvasMode::Form(data) => {
+                let mut handler = FormHandler::new(ctx, &self.config.form_config, finished_sender);
+                handler.setup(ctx, data);
+                LayoutChildHandler::Form(Box::new(handler))
+            }
+
+            CanvasMode::Progress(data) => {
+                let mut handler =
+                    ProgressHandler::new(ctx, &self.config.progress_config, finished_sender);
+                handler.setup(ctx, data);
+                LayoutChildHandler::Progress(handler)
+            }
+        }
+    }
+
+    // moves focus to the first focusable child
+    fn focus_first(&mut self) -> bool {
+        self.focused_child = None;
+        self.focus_next()
+    }
+
+    // moves focus to the next focusable child (going into nested layouts),
+    // returns false if there's nothing left to focus in this layout
+    fn focus_next(&mut self) -> bool {
+        if let Some(focused) = self.focused_child {
+            if let LayoutChildHandler::Layout(layout) = &mut self.children[focused].handler {
+                if layout.focus_next() {
+                    return true;
+                }
+            }
+        }
+
+        let start = self.focused_child.map(|i| i + 1).unwrap_or(0);
+        for i in start..self.children.len() {
+            if self.children[i].handler.focusable() {
+                self.focused_child = Some(i);
+
+                if let LayoutChildHandler::Layout(layout) = &mut self.children[i].handler {
+                    layout.focus_first();
+                }
+
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // moves focus to the next focusable child that doesn't have a value yet (wrapping around)
+    fn focus_next_empty(&mut self) {
+        let len = self.children.len();
+        let start = self.focused_child.map(|i| i + 1).unwrap_or(0);
+
+        for offset in 0..len {
+            let i = (start + offset) % len;
+            let child = &mut self.children[i];
+
+            if child.value.is_none() && child.handler.focusable() {
+                self.focused_child = Some(i);
+
+                if let LayoutChildHandler::Layout(layout) = &mut child.handler {
+                    layout.focus_first();
+                }
+
+                return;
+            }
+        }
+    }
+
+    // picks up values sent by children (passing their events on), and finishes the whole layout
+    // once every focusable child has one
+    fn collect_values(&mut self) {
+        let mut focused_child_finished = false;
+
+        for (i, child) in self.children.iter_mut().enumerate() {
+            while let Ok(message) = child.finished_receiver.try_recv() {
+                match message {
+                    FinishedMessage::UserInput(value) => {
+                        child.value = Some(value);
+
+                        if Some(i) == self.focused_child {
+                            focused_child_finished = true;
+                        }
+                    }
+
+                    // a child that failed fails the whole layout
+                    FinishedMessage::Failed(e) if !self.finished => {
+                        self.finished = true;
+                        self.finished_sender
+                            .send(FinishedMessage::Failed(e))
+                            .unwrap();
+                    }
+
+                    // events (like a Select's query_changed) are for the plugin, not the layout
+                    FinishedMessage::Event(event, data) => self
+                        .finished_sender
+                        .send(FinishedMessage::Event(event, data))
+                        .unwrap(),
+
+                    _ => {}
+                }
+            }
+        }
+
+        if self.finished {
+            return;
+        }
+
+        let all_finished = self
+            .children
+            .iter()
+            .all(|c| c.value.is_some() || !c.handler.focusable());
+
+        if all_finished {
+            self.finish();
+        } else if focused_child_finished {
+            self.focus_next_empty();
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        self.finished = true;
+
+        let values = self
+            .children
+            .iter()
+            .map(|c| {
+                let value = c.value.clone().unwrap_or(serde_json::Value::Null);
+                (c.id.clone(), value)
+            })
+            .collect::<serde_json::Map<_, _>>();
+
+        self.finished_sender
+            .send(FinishedMessage::UserInput(values.into()))
+            .unwrap();
+    }
+
+    fn focused_handler(&mut self) -> Option<&mut dyn LayoutChildDispatch> {
+        match self.focused_child {
+            Some(i) => Some(self.children[i].handler.get_mut()),
+            None => None,
+        }
+    }
+
+    // The focused child if it's an InputText (taking the fields instead of self, so the
+    // completions can be used at the same time)
+    fn focused_input_text(
+        children: &mut [LayoutChildState],
+        focused_child: Option<usize>,
+    ) -> Option<&mut InputTextHandler> {
+        match focused_child.map(|i| &mut children[i].handler) {
+            Some(LayoutChildHandler::InputText(input)) => Some(input),
+            _ => None,
+        }
+    }
+
+    // Gives Tab to the focused child (going into nested layouts), returns whether it was used
+    fn tab_focused_child(&mut self, ctx: &Context) -> bool {
+        let focused_child = match self.focused_child {
+            Some(i) => i,
+            None => return false,
+        };
+
+        match &mut self.children[focused_child].handler {
+            LayoutChildHandler::Layout(layout) => layout.tab_focused_child(ctx),
+            LayoutChildHandler::InputText(input) => self.completions.complete(ctx, input),
+            handler => handler.get_mut().handle_tab(ctx),
+        }
+    }
+
+    // Sets where Tab gets completions from, for this layout and the ones in it
+    pub fn set_completion(&mut self, completion: Completion) {
+        self.completions.completion = completion;
+
+        for child in &mut self.children {
+            if let LayoutChildHandler::Layout(layout) = &mut child.handler {
+                layout.set_completion(completion);
+            }
+        }
+    }
+
+    // Shows the completions a plugin sent for the focused InputText, returns false if
+    // no InputText is focused
+    pub fn show_completions(&mut self, ctx: &Context, completions: Vec<String>) -> bool {
+        if let Some(i) = self.focused_child {
+            if let LayoutChildHandler::Layout(layout) = &mut self.children[i].handler {
+                return layout.show_completions(ctx, completions);
+            }
+        }
+
+        match Self::focused_input_text(&mut self.children, self.focused_child) {
+            Some(input) => {
+                self.completions.show(ctx, input, completions);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl CanvasModeHandler for LayoutHandler {
+    type ConfigData = LayoutConfig;
+    type SetupData = LayoutData;
+
+    fn new(
+        ctx: &Context,
+        config: &Self::ConfigData,
+        finished_sender: Sender<FinishedMessage>,
+    ) -> Self {
+        Self {
+            config: config.clone(),
+            completions: Completions::new(ctx, &config.select_config, finished_sender.clone()),
+            finished_sender,
+            children: vec![],
+            focused_child: None,
+            finished: false,
+        }
+    }
+
+    fn setup(&mut self, ctx: &Context, data: Self::SetupData) {
+        self.finished = false;
+        self.children.clear();
+        self.completions.hide();
+
+        // the values are keyed by id, so children with the same one would overwrite each other
+        let mut ids = HashSet::new();
+        if let Some(child) = data.children.iter().find(|c| !ids.insert(&c.id)) {
+            self.finished = true;
+            let e = Error::Layout(format!("More than one child has the id {}", child.id));
+            self.finished_sender
+                .send(FinishedMessage::Failed(e))
+                .unwrap();
+            return;
+        }
+
+        for child in data.children {
+            let (sender, receiver) = channel();
+            let handler = self.create_child(ctx, data.direction, child.mode, sender);
+
+            self.children.push(LayoutChildState {
+                id: child.id,
+                handler,
+                finished_receiver: receiver,
+                value: None,
+            });
+        }
+
+        // place the children one after another, with the whole thing centered on the layout's position
+        let spacing = data
+            .spacing
+            .map(|s| s as f32)
+            .unwrap_or(self.config.spacing);
+
+        let sizes = self
+            .children
+            .iter()
+            .map(|c| {
+                let bounds = c.handler.get().bounds();
+                Vec2::new(bounds.w, bounds.h)
+            })
+            .collect::<Vec<_>>();
+
+        let gaps = spacing * (sizes.len().max(1) - 1) as f32;
+        let mut position = match data.direction {
+            LayoutDirection::Row => {
+                self.config.x_position - (sizes.iter().map(|s| s.x).sum::<f32>() + gaps) / 2.0
+            }
+            LayoutDirection::Column => {
+                self.config.y_position - (sizes.iter().map(|s| s.y).sum::<f32>() + gaps) / 2.0
+            }
+        };
+
+        for (child, size) in self.children.iter_mut().zip(sizes) {
+            let center = match data.direction {
+                LayoutDirection::Row => {
+                    let center = Vec2::new(position + size.x / 2.0, self.config.y_position);
+                    position += size.x + spacing;
+                    center
+                }
+                LayoutDirection::Column => {
+                    let center = Vec2::new(self.config.x_position, position + size.y / 2.0);
+                    position += size.y + spacing;
+                    center
+                }
+            };
+
+            child.handler.get_mut().set_center(center);
+        }
+
+        self.focus_first();
+
+        // an image that fails to load fails the whole layout straight away, and a layout with
+        // nothing to focus (like only images) finishes straight away, so both are picked up here
+        self.collect_values();
+    }
+
+    fn update(&mut self, ctx: &Context) {
+        for child in &mut self.children {
+            child.handler.get_mut().update(ctx);
+        }
+
+        self.collect_values();
+    }
+
+    fn draw(&self, ctx: &Context, draw_list: &mut DrawList) {
+        for (i, child) in self.children.iter().enumerate() {
+            let handler = child.handler.get();
+
+            // draw an outline around the focused child (nested layouts draw their own)
+            let is_nested_layout = matches!(child.handler, LayoutChildHandler::Layout(_));
+            if Some(i) == self.focused_child && !is_nested_layout {
+                let mut focus_rect = handler.bounds();
+                focus_rect.x -= self.config.focus_outline_width;
+                focus_rect.y -= self.config.focus_outline_width;
+                focus_rect.w += 2.0 * self.config.focus_outline_width;
+                focus_rect.h += 2.0 * self.config.focus_outline_width;
+
+                draw_list.rect(&focus_rect, &self.config.focus_outline_color);
+            }
+
+            handler.draw(ctx, draw_list);
+        }
+
+        if self.completions.shown {
+            self.completions.draw(ctx, draw_list);
+        }
+    }
+
+    fn handle_text_input(&mut self, ctx: &Context, inputted_char: char) {
+        self.completions.hide();
+        if let Some(handler) = self.focused_handler() {
+            handler.handle_text_input(ctx, inputted_char);
+        }
+
+        self.collect_values();
+    }
+
+    fn handle_backspace(&mut self, ctx: &Context) {
+        self.completions.hide();
+        if let Some(handler) = self.focused_handler() {
+            handler.handle_backspace(ctx);
+        }
+
+        self.collect_values();
+    }
+
+    fn handle_enter(&mut self, ctx: &Context) {
+        // enter picks a completion when they're shown, instead of saving the child's value
+        if let Some(input) = Self::focused_input_text(&mut self.children, self.focused_child) {
+            if self.completions.handle_enter(ctx, input) {
+                return;
+            }
+        }
+
+        match self.focused_handler() {
+            Some(handler) => handler.handle_enter(ctx),
+
+            // if nothing can be focused (e.g. a layout of only images), enter just closes the layout
+            None => self.finish(),
+        }
+
+        self.collect_values();
+    }
+
+    fn handle_arrow_key(&mut self, ctx: &Context, key: Key) {
+        if self.completions.handle_arrow_key(ctx, key) {
+            return;
+        }
+
+        if let Some(handler) = self.focused_handler() {
+            handler.handle_arrow_key(ctx, key);
+        }
+
+        self.collect_values();
+    }
+
+    fn handle_shortcut(&mut self, ctx: &Context, shortcut: Shortcut) {
+        self.completions.hide();
+
+        if let Some(handler) = self.focused_handler() {
+            handler.handle_shortcut(ctx, shortcut);
+        }
+
+        self.collect_values();
+    }
+
+    // the focused child gets Tab first (for completions, or going through a form's fields),
+    // otherwise it moves focus to the next child
+    fn handle_tab(&mut self, ctx: &Context) -> bool {
+        if !self.tab_focused_child(ctx) {
+            self.completions.hide();
+
+            if !self.focus_next() {
+                // wrap around to the start
+                self.focus_first();
+            }
+        }
+
+        self.collect_values();
+        true
+    }
+
+    fn bounds(&self) -> Rect {
+        let mut bounds: Option<Rect> = None;
+
+        for child in &self.children {
+            let child_bounds = child.handler.get().bounds();
+
+            bounds = Some(match bounds {
+                Some(b) => b.combine_with(child_bounds),
+                None => child_bounds,
+            });
+        }
+
+        bounds.unwrap_or(Rect::new(
+            self.config.x_position,
+            self.config.y_position,
+            0.0,
+            0.0,
+        ))
+    }
+
+    fn set_center(&mut self, center: Vec2) {
+        let bounds = CanvasModeHandler::bounds(self);
+        let offset = center - Vec2::new(bounds.x + bounds.w / 2.0, bounds.y + bounds.h / 2.0);
+
+        self.config.x_position += offset.x;
+        self.config.y_position += offset.y;
+
+        for child in &mut self.children {
+            let child_bounds = child.handler.get().bounds();
+            let child_center = Vec2::new(
+                child_bounds.x + child_bounds.w / 2.0,
+                child_bounds.y + child_bounds.h / 2.0,
+            );
+
+            child.handler.get_mut().set_center(child_center + offset);
+        }
+    }
+}
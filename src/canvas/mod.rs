@@ -1,20 +1,38 @@
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{channel, Receiver, Sender};
 
-use ggez::{
-    graphics::{Color, DrawMode, DrawParam, Mesh, Rect},
-    winit::event::VirtualKeyCode,
-    Context,
-};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::app::FinishedMessage;
+use crate::{
+    app::FinishedMessage,
+    completion::Completion,
+    context::Context,
+    graphics::{DrawList, Rect, Vec2},
+    input::{Key, Shortcut},
+    theme::Theme,
+};
+
+pub use self::{
+    form::FormConfig, image::ImageConfig, input_text::InputTextConfig, layout::LayoutConfig,
+    progress::ProgressConfig, select::SelectConfig,
+};
 
 use self::{
-    input_text::{InputTextConfig, InputTextHandler},
-    select::{SelectConfig, SelectHandler},
+    completions::Completions,
+    form::{FormData, FormHandler},
+    image::{ImageData, ImageHandler},
+    input_text::InputTextHandler,
+    layout::{LayoutData, LayoutHandler},
+    progress::{ProgressData, ProgressHandler},
+    select::{SelectData, SelectHandler},
 };
 
+mod completions;
+mod form;
+mod image;
 mod input_text;
+mod layout;
+mod progress;
 mod select;
 
 trait CanvasModeHandler {
@@ -23,159 +41,388 @@ trait CanvasModeHandler {
 
     // called to initialize data needed for every use (like fonts, etc.)
     fn new(
-        ctx: &mut Context,
+        ctx: &Context,
         config: &Self::ConfigData,
         finished_sender: Sender<FinishedMessage>,
     ) -> Self;
 
     // called to set the widget up (with data like the width, height, etc.)
-    fn setup(&mut self, ggez_ctx: &mut Context, data: Self::SetupData);
+    fn setup(&mut self, ctx: &Context, data: Self::SetupData);
 
-    fn draw(
-        &self,
-        ggez_ctx: &mut Context,
-        ggez_canvas: &mut ggez::graphics::Canvas,
-        canvas_ctx: &CanvasContext,
-    );
+    // called every frame, for widgets that need to do something over time (like timeouts)
+    fn update(&mut self, _ctx: &Context) {}
 
-    fn handle_text_input(&mut self, ggez_ctx: &Context, inputted_char: char);
+    fn draw(&self, ctx: &Context, draw_list: &mut DrawList);
 
-    fn handle_backspace(&mut self, ggez_ctx: &Context);
+    fn handle_text_input(&mut self, ctx: &Context, inputted_char: char);
 
-    fn handle_enter(&mut self, ggez_ctx: &Context);
+    fn handle_backspace(&mut self, ctx: &Context);
 
-    fn handle_arrow_key(&mut self, ggez_ctx: &Context, keycode: VirtualKeyCode);
-}
+    fn handle_enter(&mut self, ctx: &Context);
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-pub enum CanvasMode {
-    InputText,
-    Select(Vec<String>),
-}
+    fn handle_arrow_key(&mut self, ctx: &Context, key: Key);
 
-pub struct CanvasContext {
-    // this mesh is used to draw any rectangle on screen
-    // at first I had a separate rect mesh like this for every rectangle, but
-    // it turns out you can just reuse a single one, so that's what this is for
-    pub rect_mesh: Mesh,
-}
+    // key combinations like Ctrl+R, which most widgets don't use
+    fn handle_shortcut(&mut self, _ctx: &Context, _shortcut: Shortcut) {}
 
-impl CanvasContext {
-    pub fn draw_rect(&self, ggez_canvas: &mut ggez::graphics::Canvas, rect: &Rect, color: &Color) {
-        ggez_canvas.draw(
-            &self.rect_mesh,
-            DrawParam::new().dest_rect(*rect).color(*color),
-        );
+    // returns whether the widget used it, a layout moves focus to its next child otherwise
+    fn handle_tab(&mut self, _ctx: &Context) -> bool {
+        false
     }
+
+    // the rect the widget currently takes up on screen (including its outline)
+    fn bounds(&self) -> Rect;
+
+    // moves the widget so it's centered on the given point (used when widgets are laid out together)
+    fn set_center(&mut self, center: Vec2);
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum CanvasMode {
+    InputText,
+    Select(SelectData),
+    Image(ImageData),
+    Layout(LayoutData),
+    Form(FormData),
+    Progress(ProgressData),
 }
 
 pub struct Canvas {
-    pub ctx: CanvasContext,
     pub current_mode: Option<CanvasMode>,
     pub handler_input_text: InputTextHandler,
     pub handler_select: SelectHandler,
-    pub finished_sender: Sender<FinishedMessage>,
+    pub handler_image: ImageHandler,
+    pub handler_layout: LayoutHandler,
+    pub handler_form: FormHandler,
+    pub handler_progress: ProgressHandler,
+    // what Tab shows under the InputText
+    pub completions: Completions,
+    // every canvas has its own channel, so what a suspended command's canvas sent waits for it to resume
+    finished_receiver: Receiver<FinishedMessage>,
+    pub theme: Theme,
 }
 
 impl Canvas {
-    pub fn new(ggez_ctx: &mut Context, finished_sender: Sender<FinishedMessage>) -> Self {
-        let rect_mesh = Mesh::new_rectangle(
-            &ggez_ctx.gfx,
-            DrawMode::fill(),
-            Rect::new(0.0, 0.0, 1.0, 1.0),
-            Color::WHITE,
-        )
-        .unwrap();
+    pub fn new(ctx: &Context, theme: &Theme) -> Self {
+        let (finished_sender, finished_receiver) = channel();
 
         Self {
-            ctx: CanvasContext { rect_mesh },
             current_mode: None,
             handler_input_text: InputTextHandler::new(
-                ggez_ctx,
-                &InputTextConfig::default(),
+                ctx,
+                &theme.input_text_config(),
                 finished_sender.clone(),
             ),
             handler_select: SelectHandler::new(
-                ggez_ctx,
-                &SelectConfig::default(),
+                ctx,
+                &theme.select_config(),
                 finished_sender.clone(),
             ),
-            finished_sender,
+            handler_image: ImageHandler::new(ctx, &theme.image_config(), finished_sender.clone()),
+            handler_layout: LayoutHandler::new(
+                ctx,
+                &theme.layout_config(),
+                finished_sender.clone(),
+            ),
+            handler_form: FormHandler::new(ctx, &theme.form_config(), finished_sender.clone()),
+            handler_progress: ProgressHandler::new(
+                ctx,
+                &theme.progress_config(),
+                finished_sender.clone(),
+            ),
+            completions: Completions::new(ctx, &theme.select_config(), finished_sender),
+            finished_receiver,
+            theme: theme.clone(),
         }
     }
 
-    pub fn set_mode(&mut self, ggez_ctx: &mut Context, mode: Option<CanvasMode>) {
+    // Recreates every handler with the given theme's configs (only if it actually changed,
+    // since that clears whatever the handlers were showing)
+    pub fn set_theme(&mut self, ctx: &Context, theme: &Theme) {
+        if &self.theme == theme {
+            return;
+        }
+
+        let current_mode = self.current_mode.take();
+        *self = Self::new(ctx, theme);
+        self.set_mode(ctx, current_mode);
+    }
+
+    pub fn set_mode(&mut self, ctx: &Context, mode: Option<CanvasMode>) {
+        // whatever the mode before sent isn't for the new one
+        while self.finished_receiver.try_recv().is_ok() {}
+
         self.current_mode = mode;
+        self.completions.hide();
 
         match &self.current_mode {
-            Some(CanvasMode::InputText) => self.handler_input_text.setup(ggez_ctx, ()),
+            Some(CanvasMode::InputText) => self.handler_input_text.setup(ctx, ()),
+
+            Some(CanvasMode::Select(options)) => self.handler_select.setup(ctx, options.clone()),
+
+            Some(CanvasMode::Image(data)) => self.handler_image.setup(ctx, data.clone()),
+
+            Some(CanvasMode::Layout(data)) => self.handler_layout.setup(ctx, data.clone()),
 
-            Some(CanvasMode::Select(options)) => {
-                self.handler_select.setup(ggez_ctx, options.clone())
+            Some(CanvasMode::Progress(data)) => self.handler_progress.setup(ctx, data.clone()),
+
+            Some(CanvasMode::Form(data)) => self.handler_form.setup(ctx, data.clone()),
+
+            None => {}
+        }
+    }
+
+    // What the current mode finished with (or an event it sent), if it sent anything
+    pub fn next_finished(&self) -> Option<FinishedMessage> {
+        self.finished_receiver.try_recv().ok()
+    }
+
+    // Changes the current mode in place if it's the same kind as the given one (e.g. updating
+    // a progress bar's percent or a select's options), otherwise just replaces it
+    pub fn patch_mode(&mut self, ctx: &Context, mode: CanvasMode) {
+        match (&mut self.current_mode, mode) {
+            (Some(CanvasMode::Progress(current)), CanvasMode::Progress(data)) => {
+                if data.percent.is_some() {
+                    current.percent = data.percent;
+                }
+                if data.label.is_some() {
+                    current.label = data.label.clone();
+                }
+
+                self.handler_progress.patch(ctx, data);
+            }
+
+            (Some(CanvasMode::Select(current)), CanvasMode::Select(data)) => {
+                current.options = data.options.clone();
+                self.handler_select.set_options(data.options);
             }
 
+            // there's nothing to change, and setting it up again would clear the inputted text
+            (Some(CanvasMode::InputText), CanvasMode::InputText) => {}
+
+            (_, mode) => self.set_mode(ctx, Some(mode)),
+        }
+    }
+
+    pub fn update(&mut self, ctx: &Context) {
+        match self.current_mode {
+            Some(CanvasMode::InputText) => self.handler_input_text.update(ctx),
+
+            Some(CanvasMode::Select(_)) => self.handler_select.update(ctx),
+
+            Some(CanvasMode::Image(_)) => self.handler_image.update(ctx),
+
+            Some(CanvasMode::Layout(_)) => self.handler_layout.update(ctx),
+
+            Some(CanvasMode::Progress(_)) => self.handler_progress.update(ctx),
+
+            Some(CanvasMode::Form(_)) => self.handler_form.update(ctx),
+
             None => {}
         }
     }
 
-    pub fn draw(&self, ggez_ctx: &mut Context, ggez_canvas: &mut ggez::graphics::Canvas) {
+    // Where the current mode is drawn, if there is one
+    pub fn bounds(&self) -> Option<Rect> {
+        Some(match self.current_mode {
+            Some(CanvasMode::InputText) if self.completions.shown => self.completions.bounds(),
+            Some(CanvasMode::InputText) => self.handler_input_text.bounds(),
+
+            Some(CanvasMode::Select(_)) => self.handler_select.bounds(),
+
+            Some(CanvasMode::Image(_)) => self.handler_image.bounds(),
+
+            Some(CanvasMode::Layout(_)) => self.handler_layout.bounds(),
+
+            Some(CanvasMode::Progress(_)) => self.handler_progress.bounds(),
+
+            Some(CanvasMode::Form(_)) => self.handler_form.bounds(),
+
+            None => return None,
+        })
+    }
+
+    pub fn draw(&self, ctx: &Context, draw_list: &mut DrawList) {
+        match self.current_mode {
+            Some(CanvasMode::InputText) if self.completions.shown => {
+                self.completions.draw(ctx, draw_list)
+            }
+            Some(CanvasMode::InputText) => self.handler_input_text.draw(ctx, draw_list),
+
+            Some(CanvasMode::Select(_)) => self.handler_select.draw(ctx, draw_list),
+
+            Some(CanvasMode::Image(_)) => self.handler_image.draw(ctx, draw_list),
+
+            Some(CanvasMode::Layout(_)) => self.handler_layout.draw(ctx, draw_list),
+
+            Some(CanvasMode::Progress(_)) => self.handler_progress.draw(ctx, draw_list),
+
+            Some(CanvasMode::Form(_)) => self.handler_form.draw(ctx, draw_list),
+
+            None => {}
+        }
+    }
+
+    pub fn handle_text_input(&mut self, ctx: &Context, inputted_char: char) {
         match self.current_mode {
             Some(CanvasMode::InputText) => {
+                // typing goes on from what's shown, so the completions are for something else now
+                self.completions.hide();
                 self.handler_input_text
-                    .draw(ggez_ctx, ggez_canvas, &self.ctx)
+                    .handle_text_input(ctx, inputted_char)
             }
 
             Some(CanvasMode::Select(_)) => {
-                self.handler_select.draw(ggez_ctx, ggez_canvas, &self.ctx)
+                self.handler_select.handle_text_input(ctx, inputted_char)
+            }
+
+            Some(CanvasMode::Image(_)) => self.handler_image.handle_text_input(ctx, inputted_char),
+
+            Some(CanvasMode::Layout(_)) => {
+                self.handler_layout.handle_text_input(ctx, inputted_char)
             }
 
+            Some(CanvasMode::Progress(_)) => {
+                self.handler_progress.handle_text_input(ctx, inputted_char)
+            }
+
+            Some(CanvasMode::Form(_)) => self.handler_form.handle_text_input(ctx, inputted_char),
+
             None => {}
         }
     }
 
-    pub fn handle_text_input(&mut self, ggez_ctx: &Context, inputted_char: char) {
+    pub fn handle_backspace(&mut self, ctx: &Context) {
         match self.current_mode {
-            Some(CanvasMode::InputText) => self
-                .handler_input_text
-                .handle_text_input(ggez_ctx, inputted_char),
+            Some(CanvasMode::InputText) => {
+                self.completions.hide();
+                self.handler_input_text.handle_backspace(ctx)
+            }
+
+            Some(CanvasMode::Select(_)) => self.handler_select.handle_backspace(ctx),
+
+            Some(CanvasMode::Image(_)) => self.handler_image.handle_backspace(ctx),
 
-            Some(CanvasMode::Select(_)) => self
-                .handler_select
-                .handle_text_input(ggez_ctx, inputted_char),
+            Some(CanvasMode::Layout(_)) => self.handler_layout.handle_backspace(ctx),
+
+            Some(CanvasMode::Progress(_)) => self.handler_progress.handle_backspace(ctx),
+
+            Some(CanvasMode::Form(_)) => self.handler_form.handle_backspace(ctx),
 
             None => {}
         }
     }
 
-    pub fn handle_backspace(&mut self, ggez_ctx: &Context) {
+    pub fn handle_enter(&mut self, ctx: &Context) {
         match self.current_mode {
-            Some(CanvasMode::InputText) => self.handler_input_text.handle_backspace(ggez_ctx),
+            // enter picks a completion when they're shown, instead of submitting
+            Some(CanvasMode::InputText) => {
+                let picked = self
+                    .completions
+                    .handle_enter(ctx, &mut self.handler_input_text);
+                if !picked {
+                    self.handler_input_text.handle_enter(ctx)
+                }
+            }
+
+            Some(CanvasMode::Select(_)) => self.handler_select.handle_enter(ctx),
+
+            Some(CanvasMode::Image(_)) => self.handler_image.handle_enter(ctx),
+
+            Some(CanvasMode::Layout(_)) => self.handler_layout.handle_enter(ctx),
 
-            Some(CanvasMode::Select(_)) => self.handler_select.handle_backspace(ggez_ctx),
+            Some(CanvasMode::Progress(_)) => self.handler_progress.handle_enter(ctx),
+
+            Some(CanvasMode::Form(_)) => self.handler_form.handle_enter(ctx),
 
             None => {}
         }
     }
 
-    pub fn handle_enter(&mut self, ggez_ctx: &Context) {
+    pub fn handle_arrow_key(&mut self, ctx: &Context, key: Key) {
         match self.current_mode {
-            Some(CanvasMode::InputText) => self.handler_input_text.handle_enter(ggez_ctx),
+            Some(CanvasMode::InputText) => {
+                let used = self.completions.handle_arrow_key(ctx, key);
+                if !used {
+                    self.handler_input_text.handle_arrow_key(ctx, key)
+                }
+            }
 
-            Some(CanvasMode::Select(_)) => self.handler_select.handle_enter(ggez_ctx),
+            Some(CanvasMode::Select(_)) => self.handler_select.handle_arrow_key(ctx, key),
+
+            Some(CanvasMode::Image(_)) => self.handler_image.handle_arrow_key(ctx, key),
+
+            Some(CanvasMode::Layout(_)) => self.handler_layout.handle_arrow_key(ctx, key),
+
+            Some(CanvasMode::Progress(_)) => self.handler_progress.handle_arrow_key(ctx, key),
+
+            Some(CanvasMode::Form(_)) => self.handler_form.handle_arrow_key(ctx, key),
 
             None => {}
         }
     }
 
-    pub fn handle_arrow_key(&mut self, ggez_ctx: &Context, keycode: VirtualKeyCode) {
+    pub fn handle_shortcut(&mut self, ctx: &Context, shortcut: Shortcut) {
         match self.current_mode {
             Some(CanvasMode::InputText) => {
-                self.handler_input_text.handle_arrow_key(ggez_ctx, keycode)
+                self.completions.hide();
+                self.handler_input_text.handle_shortcut(ctx, shortcut)
             }
 
-            Some(CanvasMode::Select(_)) => self.handler_select.handle_arrow_key(ggez_ctx, keycode),
+            Some(CanvasMode::Select(_)) => self.handler_select.handle_shortcut(ctx, shortcut),
+
+            Some(CanvasMode::Image(_)) => self.handler_image.handle_shortcut(ctx, shortcut),
+
+            Some(CanvasMode::Layout(_)) => self.handler_layout.handle_shortcut(ctx, shortcut),
+
+            Some(CanvasMode::Progress(_)) => self.handler_progress.handle_shortcut(ctx, shortcut),
+
+            Some(CanvasMode::Form(_)) => self.handler_form.handle_shortcut(ctx, shortcut),
 
             None => {}
         }
     }
+
+    // there's nothing around the current mode to give Tab to, so it doesn't matter whether it's used
+    pub fn handle_tab(&mut self, ctx: &Context) {
+        match self.current_mode {
+            Some(CanvasMode::InputText) => {
+                self.completions.complete(ctx, &mut self.handler_input_text)
+            }
+
+            Some(CanvasMode::Select(_)) => self.handler_select.handle_tab(ctx),
+
+            Some(CanvasMode::Image(_)) => self.handler_image.handle_tab(ctx),
+
+            Some(CanvasMode::Layout(_)) => self.handler_layout.handle_tab(ctx),
+
+            Some(CanvasMode::Progress(_)) => self.handler_progress.handle_tab(ctx),
+
+            Some(CanvasMode::Form(_)) => self.handler_form.handle_tab(ctx),
+
+            None => false,
+        };
+    }
+
+    // Sets where Tab gets completions from, for the InputText and the ones in a layout
+    pub fn set_completion(&mut self, completion: Completion) {
+        self.completions.completion = completion;
+        self.handler_layout.set_completion(completion);
+    }
+
+    // Shows the completions a plugin sent for the focused InputText's text, returns false if
+    // there's no InputText to show them for
+    pub fn show_completions(&mut self, ctx: &Context, completions: Vec<String>) -> bool {
+        match self.current_mode {
+            Some(CanvasMode::InputText) => {
+                self.completions
+                    .show(ctx, &mut self.handler_input_text, completions);
+                true
+            }
+
+            Some(CanvasMode::Layout(_)) => self.handler_layout.show_completions(ctx, completions),
+
+            _ => false,
+        }
+    }
 }
@@ -0,0 +1,281 @@
+use std::{f32::consts::PI, rc::Rc, sync::mpsc::Sender};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::FinishedMessage,
+    clock::Clock,
+    context::Context,
+    font::FontChain,
+    graphics::{Color, DrawList, Rect, Text, TextFragment, Vec2},
+    input::Key,
+    screen::Screen,
+};
+
+use super::CanvasModeHandler;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct ProgressData {
+    // from 0 to 100, if not given a spinner is shown instead of a bar
+    pub percent: Option<f32>,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgressConfig {
+    pub bar_width: f32,
+    pub bar_height: f32,
+    pub spinner_radius: f32,
+    pub spinner_dot_size: f32,
+    pub spinner_dots: usize,
+    pub spinner_step_duration: u128, // amount of time in milliseconds before the spinner moves by one dot
+    pub font_size: f32,
+    pub padding: f32,
+    pub spacing: f32, // space in between the label and the bar
+    #[serde(with = "crate::theme::color")]
+    pub text_color: Color,
+    #[serde(with = "crate::theme::color")]
+    pub bar_color: Color,
+    #[serde(with = "crate::theme::color")]
+    pub bar_background_color: Color,
+    pub background_outline_width: f32,
+    #[serde(with = "crate::theme::color")]
+    pub background_outline_color: Color,
+    #[serde(with = "crate::theme::color")]
+    pub background_color: Color,
+    #[serde(skip)]
+    pub y_position: f32,
+    #[serde(skip)]
+    pub x_position: f32,
+    #[serde(skip)]
+    pub font: FontChain,
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        Self {
+            bar_width: 800.0,
+            bar_height: 40.0,
+            spinner_radius: 40.0,
+            spinner_dot_size: 14.0,
+            spinner_dots: 8,
+            spinner_step_duration: 100,
+            font_size: 32.0,
+            padding: 20.0,
+            spacing: 15.0,
+            text_color: Color::BLACK,
+            bar_color: Color::from_rgb(80, 140, 255),
+            bar_background_color: Color::from_rgba(0, 0, 0, 40),
+            background_outline_width: 2.0,
+            background_outline_color: Color::BLACK,
+            background_color: Color::WHITE,
+            y_position: Screen::default().center_y(),
+            x_position: Screen::default().center_x(),
+            font: Default::default(),
+        }
+    }
+}
+
+pub struct ProgressHandler {
+    pub config: ProgressConfig,
+    pub finished_sender: Sender<FinishedMessage>,
+    pub background_rect: Rect,
+    pub percent: Option<f32>,
+    pub label: Option<Text>,
+    pub percent_text: Text,
+    // the spinner turns with it
+    pub clock: Rc<dyn Clock>,
+}
+
+impl ProgressHandler {
+    pub fn get_outline_rect(&self) -> Rect {
+        let mut outline_rect = self.background_rect;
+        outline_rect.x -= self.config.background_outline_width;
+        outline_rect.y -= self.config.background_outline_width;
+        outline_rect.w += 2.0 * self.config.background_outline_width;
+        outline_rect.h += 2.0 * self.config.background_outline_width;
+
+        outline_rect
+    }
+
+    fn make_text(&self, text: String) -> Text {
+        self.config.font.text(TextFragment {
+            text,
+            color: Some(self.config.text_color),
+            scale: Some(self.config.font_size),
+            ..Default::default()
+        })
+    }
+
+    fn set_percent(&mut self, percent: f32) {
+        let percent = percent.clamp(0.0, 100.0);
+
+        self.percent = Some(percent);
+        self.percent_text = self.make_text(format!("{}%", percent.round()));
+    }
+
+    // Recomputes the background rect from the label and the bar/spinner, keeping it centered
+    fn resize(&mut self, ctx: &Context) {
+        let indicator_height = match self.percent {
+            Some(_) => self.config.bar_height,
+            None => 2.0 * self.config.spinner_radius + self.config.spinner_dot_size,
+        };
+
+        let mut height = 2.0 * self.config.padding + indicator_height;
+        if let Some(label) = &self.label {
+            height += ctx.measure(label).h + self.config.spacing;
+        }
+
+        let width = 2.0 * self.config.padding + self.config.bar_width;
+
+        self.background_rect = Rect::new(
+            self.config.x_position - width / 2.0,
+            self.config.y_position - height / 2.0,
+            width,
+            height,
+        );
+    }
+
+    // Changes only what's given, so a plugin can e.g. update the percent without resending the label
+    pub fn patch(&mut self, ctx: &Context, data: ProgressData) {
+        if let Some(percent) = data.percent {
+            self.set_percent(percent);
+        }
+
+        if let Some(label) = data.label {
+            let mut text = self.make_text(label);
+            text.set_bounds(Vec2::new(self.config.bar_width, f32::MAX));
+            self.label = Some(text);
+        }
+
+        self.resize(ctx);
+    }
+}
+
+impl CanvasModeHandler for ProgressHandler {
+    type ConfigData = ProgressConfig;
+    type SetupData = ProgressData;
+
+    fn new(
+        ctx: &Context,
+        config: &Self::ConfigData,
+        finished_sender: Sender<FinishedMessage>,
+    ) -> Self {
+        Self {
+            config: config.clone(),
+            finished_sender,
+            background_rect: Rect::new(config.x_position, config.y_position, 0.0, 0.0),
+            percent: None,
+            label: None,
+            percent_text: Text::new(""),
+            clock: ctx.clock(),
+        }
+    }
+
+    fn setup(&mut self, ctx: &Context, data: Self::SetupData) {
+        self.percent = None;
+        self.label = None;
+
+        self.patch(ctx, data);
+    }
+
+    fn draw(&self, ctx: &Context, draw_list: &mut DrawList) {
+        // draw background outline
+        draw_list.rect(
+            &self.get_outline_rect(),
+            &self.config.background_outline_color,
+        );
+
+        // draw background
+        draw_list.rect(&self.background_rect, &self.config.background_color);
+
+        let x = self.background_rect.x + self.config.padding;
+        let mut y = self.background_rect.y + self.config.padding;
+
+        // draw label on top
+        if let Some(label) = &self.label {
+            draw_list.text(label, Vec2::new(x, y));
+            y += ctx.measure(label).h + self.config.spacing;
+        }
+
+        match self.percent {
+            // draw bar with the percent in the middle of it
+            Some(percent) => {
+                let bar_rect = Rect::new(x, y, self.config.bar_width, self.config.bar_height);
+                draw_list.rect(&bar_rect, &self.config.bar_background_color);
+
+                let mut filled_rect = bar_rect;
+                filled_rect.w *= percent / 100.0;
+                draw_list.rect(&filled_rect, &self.config.bar_color);
+
+                let percent_rect = ctx.measure(&self.percent_text);
+                draw_list.text(
+                    &self.percent_text,
+                    Vec2::new(
+                        bar_rect.x + (bar_rect.w - percent_rect.w) / 2.0,
+                        bar_rect.y + (bar_rect.h - percent_rect.h) / 2.0,
+                    ),
+                );
+            }
+
+            // draw a spinner made of dots going around in a circle, the brightest one being the "head"
+            None => {
+                // themes can set these to anything, and 0 would divide by zero
+                let dots = self.config.spinner_dots.max(1);
+                let step = self.clock.time_since_start().as_millis()
+                    / self.config.spinner_step_duration.max(1);
+                let head = (step % dots as u128) as usize;
+
+                let center = Vec2::new(
+                    self.config.x_position,
+                    y + self.config.spinner_radius + self.config.spinner_dot_size / 2.0,
+                );
+
+                for i in 0..dots {
+                    let angle = 2.0 * PI * i as f32 / dots as f32;
+                    let dot_center =
+                        center + self.config.spinner_radius * Vec2::new(angle.cos(), angle.sin());
+
+                    let dot_rect = Rect::new(
+                        dot_center.x - self.config.spinner_dot_size / 2.0,
+                        dot_center.y - self.config.spinner_dot_size / 2.0,
+                        self.config.spinner_dot_size,
+                        self.config.spinner_dot_size,
+                    );
+
+                    // dots further behind the head fade out
+                    let distance = (head + dots - i) % dots;
+                    let mut color = self.config.bar_color;
+                    color.a = 1.0 - distance as f32 / dots as f32;
+
+                    draw_list.rect(&dot_rect, &color);
+                }
+            }
+        }
+    }
+
+    // progress isn't interactive, it's only finished by the plugin
+
+    fn handle_text_input(&mut self, _ctx: &Context, _inputted_char: char) {}
+
+    fn handle_backspace(&mut self, _ctx: &Context) {}
+
+    fn handle_enter(&mut self, _ctx: &Context) {}
+
+    fn handle_arrow_key(&mut self, _ctx: &Context, _key: Key) {}
+
+    fn bounds(&self) -> Rect {
+        self.get_outline_rect()
+    }
+
+    fn set_center(&mut self, center: Vec2) {
+        self.config.x_position = center.x;
+        self.config.y_position = center.y;
+
+        self.background_rect.x = center.x - self.background_rect.w / 2.0;
+        self.background_rect.y = center.y - self.background_rect.h / 2.0;
+    }
+}
@@ -1,29 +1,110 @@
-use std::sync::mpsc::Sender;
-
-use ggez::{
-    glam::Vec2,
-    graphics::{Color, DrawParam, PxScale, Rect, Text, TextFragment},
-    winit::event::VirtualKeyCode,
+use std::{collections::HashMap, rc::Rc, sync::mpsc::Sender, time};
+
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::FinishedMessage,
+    clock::Clock,
+    context::Context,
+    font::FontChain,
+    graphics::{Color, DrawList, Rect, Text, TextFragment, Vec2},
+    input::Key,
+    screen::Screen,
 };
 
-use crate::{app::FinishedMessage, SCREEN_HEIGHT, SCREEN_WIDTH};
-
 use super::{
     input_text::{InputTextConfig, InputTextHandler},
     CanvasModeHandler,
 };
 
-#[derive(Clone)]
+// Can be given either as just a list of options, or as an object with some extra settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(from = "SelectDataRepr")]
+pub struct SelectData {
+    pub options: Vec<String>,
+    // if true, a query_changed event is sent to the plugin whenever the user stops typing for a bit,
+    // and the options aren't filtered locally (the plugin is expected to send new ones instead)
+    pub query_changed_events: bool,
+    pub debounce_ms: Option<u64>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum SelectDataRepr {
+    Options(Vec<String>),
+    Full {
+        options: Vec<String>,
+        #[serde(default)]
+        query_changed_events: bool,
+        #[serde(default)]
+        debounce_ms: Option<u64>,
+    },
+}
+
+// The schema has both forms, since that's what's accepted
+impl JsonSchema for SelectData {
+    fn schema_name() -> String {
+        String::from("SelectData")
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        SelectDataRepr::json_schema(gen)
+    }
+}
+
+impl From<SelectDataRepr> for SelectData {
+    fn from(repr: SelectDataRepr) -> Self {
+        match repr {
+            SelectDataRepr::Options(options) => Self {
+                options,
+                ..Default::default()
+            },
+
+            SelectDataRepr::Full {
+                options,
+                query_changed_events,
+                debounce_ms,
+            } => Self {
+                options,
+                query_changed_events,
+                debounce_ms,
+            },
+        }
+    }
+}
+
+impl From<Vec<String>> for SelectData {
+    fn from(options: Vec<String>) -> Self {
+        Self {
+            options,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SelectConfig {
+    #[serde(skip)]
     pub input_text_config: InputTextConfig,
     pub max_options_shown: usize,
+    #[serde(skip)]
     pub x_position: f32,
+    #[serde(skip)]
     pub y_position: f32,
+    #[serde(with = "crate::theme::color")]
     pub text_color: Color,
     pub background_outline_width: f32,
+    #[serde(with = "crate::theme::color")]
     pub background_outline_color: Color,
+    #[serde(with = "crate::theme::color")]
     pub background_color: Color,
+    #[serde(with = "crate::theme::color")]
     pub selected_option_background_color: Color,
+    pub query_debounce_ms: u64,
+    #[serde(skip)]
+    pub option_font: FontChain,
 }
 
 impl Default for SelectConfig {
@@ -31,13 +112,15 @@ impl Default for SelectConfig {
         Self {
             input_text_config: Default::default(),
             max_options_shown: 5,
-            x_position: SCREEN_WIDTH / 2.0,
-            y_position: SCREEN_HEIGHT / 2.0,
+            x_position: Screen::default().center_x(),
+            y_position: Screen::default().center_y(),
             text_color: Color::BLACK,
             background_outline_width: 2.0,
             background_outline_color: Color::BLACK,
             background_color: Color::WHITE,
             selected_option_background_color: Color::from_rgba(0, 0, 0, 100),
+            query_debounce_ms: 200,
+            option_font: Default::default(),
         }
     }
 }
@@ -52,6 +135,14 @@ pub struct SelectHandler {
     pub filtered_options_indexes: Vec<usize>,
     pub filtered_options_visible_indexes: Vec<usize>,
     pub selected_option: Option<usize>, // from 0 to filtered_options_visible_indexes.len()
+    pub query_changed_events: bool,
+    // whether the options are filtered by the query (not when the plugin sends them for it)
+    pub filtering: bool,
+    pub query_debounce: time::Duration,
+    pub query_changed_at: Option<time::Duration>, // when the query last changed, if no event was sent for it yet
+    pub clock: Rc<dyn Clock>,
+    // how much options are boosted in the ranking (see Frecency), set for a Select with a remember_key
+    pub scores: HashMap<String, f32>,
 }
 
 impl SelectHandler {
@@ -65,6 +156,77 @@ impl SelectHandler {
         outline_rect
     }
 
+    // The option that would be picked if enter was pressed right now
+    pub fn selected_value(&self) -> Option<String> {
+        self.selected_option.map(|selected_option| {
+            let index = self.filtered_options_visible_indexes[selected_option];
+            self.all_options_strings[index].clone()
+        })
+    }
+
+    // Selects the given option (scrolling to it if necessary), does nothing if it doesn't exist
+    pub fn select_value(&mut self, value: &str) {
+        let position = self
+            .filtered_options_indexes
+            .iter()
+            .position(|i| self.all_options_strings[*i] == value);
+
+        if let Some(position) = position {
+            let shown = self.config.max_options_shown;
+            let start = position.min(self.filtered_options_indexes.len().saturating_sub(shown));
+
+            self.filtered_options_visible_indexes = self.filtered_options_indexes
+                [start..(start + shown).min(self.filtered_options_indexes.len())]
+                .to_vec();
+            self.selected_option = Some(position - start);
+        }
+    }
+
+    // Replaces the options while keeping what the user typed, and keeps the selected option
+    // selected if it's still there
+    pub fn set_options(&mut self, options: Vec<String>) {
+        let selected = self.selected_value();
+
+        self.all_options_strings = options;
+        self.all_options = self
+            .all_options_strings
+            .iter()
+            .map(|option| {
+                self.config.option_font.text(TextFragment {
+                    text: option.clone(),
+                    color: Some(self.config.text_color),
+                    scale: Some(self.input_text_handler.config.text_font_size),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        self.filter_options();
+
+        if let Some(selected) = selected {
+            self.select_value(&selected);
+        }
+    }
+
+    // Replaces the scores options are ranked by, selecting the first option again
+    pub fn set_scores(&mut self, scores: HashMap<String, f32>) {
+        self.scores = scores;
+        self.filter_options();
+    }
+
+    // Turns filtering the options by the query on or off, for options that already fit it
+    pub fn set_filtering(&mut self, filtering: bool) {
+        self.filtering = filtering;
+        self.filter_options();
+    }
+
+    // Marks that the query changed, so an event gets sent for it once the user stops typing
+    fn query_changed(&mut self) {
+        if self.query_changed_events {
+            self.query_changed_at = Some(self.clock.time_since_start());
+        }
+    }
+
     fn strings_match(original: &str, query: &str) -> bool {
         let lowercase_original = original.trim().to_lowercase();
         let lowercase_query = query.trim().to_lowercase();
@@ -73,24 +235,32 @@ impl SelectHandler {
     }
 
     fn filter_options(&mut self) {
-        self.filtered_options_indexes.clear();
-        self.filtered_options_visible_indexes.clear();
-
-        let mut num_visible = 0;
-        for i in 0..self.all_options.len() {
-            let option = &self.all_options_strings[i];
-            let query = &self.input_text_handler.entire_text;
-
-            if Self::strings_match(option, query) {
-                self.filtered_options_indexes.push(i);
-
-                if num_visible < self.config.max_options_shown {
-                    self.filtered_options_visible_indexes.push(i);
-                    num_visible += 1;
-                }
-            }
+        let query = &self.input_text_handler.entire_text;
+
+        let mut indexes: Vec<usize> = (0..self.all_options.len())
+            .filter(|&i| {
+                !self.filtering || Self::strings_match(&self.all_options_strings[i], query)
+            })
+            .collect();
+
+        // options with higher scores go first, the rest keep their order
+        if !self.scores.is_empty() {
+            let score = |i: &usize| {
+                self.scores
+                    .get(&self.all_options_strings[*i])
+                    .copied()
+                    .unwrap_or(0.0)
+            };
+            indexes.sort_by(|a, b| score(b).total_cmp(&score(a)));
         }
 
+        self.filtered_options_visible_indexes = indexes
+            .iter()
+            .take(self.config.max_options_shown)
+            .copied()
+            .collect();
+        self.filtered_options_indexes = indexes;
+
         // reset selected option
         if self.filtered_options_visible_indexes.is_empty() {
             self.selected_option = None;
@@ -102,10 +272,10 @@ impl SelectHandler {
 
 impl CanvasModeHandler for SelectHandler {
     type ConfigData = SelectConfig;
-    type SetupData = Vec<String>;
+    type SetupData = SelectData;
 
     fn new(
-        ctx: &mut ggez::Context,
+        ctx: &Context,
         config: &Self::ConfigData,
         finished_sender: Sender<FinishedMessage>,
     ) -> Self {
@@ -134,6 +304,8 @@ impl CanvasModeHandler for SelectHandler {
         let input_text_handler =
             InputTextHandler::new(ctx, &config.input_text_config, finished_sender.clone());
 
+        let query_debounce = time::Duration::from_millis(config.query_debounce_ms);
+
         Self {
             config,
             finished_sender,
@@ -144,101 +316,103 @@ impl CanvasModeHandler for SelectHandler {
             filtered_options_indexes: vec![],
             filtered_options_visible_indexes: vec![],
             selected_option: None,
+            query_changed_events: false,
+            filtering: true,
+            query_debounce,
+            query_changed_at: None,
+            clock: ctx.clock(),
+            scores: HashMap::new(),
         }
     }
 
-    fn setup(&mut self, ggez_ctx: &mut ggez::Context, data: Self::SetupData) {
-        self.input_text_handler.setup(ggez_ctx, ());
+    fn setup(&mut self, ctx: &Context, data: Self::SetupData) {
+        self.input_text_handler.setup(ctx, ());
+
+        self.query_changed_events = data.query_changed_events;
+        self.filtering = !data.query_changed_events;
+        self.query_debounce =
+            time::Duration::from_millis(data.debounce_ms.unwrap_or(self.config.query_debounce_ms));
+        self.query_changed_at = None;
 
         self.all_options.clear();
         self.all_options_strings.clear();
-        for option in data {
+        for option in data.options {
             self.all_options_strings.push(option.clone());
 
             let fragment = TextFragment {
                 text: option,
                 color: Some(self.config.text_color),
-                scale: Some(PxScale::from(self.input_text_handler.config.text_font_size)),
+                scale: Some(self.input_text_handler.config.text_font_size),
                 ..Default::default()
             };
 
-            self.all_options.push(Text::new(fragment.clone()));
+            self.all_options
+                .push(self.config.option_font.text(fragment));
         }
 
-        self.filtered_options_indexes.clear();
-        self.filtered_options_visible_indexes.clear();
-        for i in 0..self.all_options.len() {
-            self.filtered_options_indexes.push(i);
+        // the scores are set again by whatever needs them
+        self.scores.clear();
+        self.filter_options();
+    }
+
+    fn update(&mut self, _ctx: &Context) {
+        if let Some(query_changed_at) = self.query_changed_at {
+            let elapsed = self.clock.time_since_start() - query_changed_at;
 
-            if i < self.config.max_options_shown {
-                self.filtered_options_visible_indexes.push(i);
+            if elapsed >= self.query_debounce {
+                self.query_changed_at = None;
+
+                self.finished_sender
+                    .send(FinishedMessage::Event(
+                        String::from("query_changed"),
+                        self.input_text_handler.entire_text.clone().into(),
+                    ))
+                    .unwrap();
             }
         }
-
-        self.selected_option = Some(0);
     }
 
-    fn draw(
-        &self,
-        ggez_ctx: &mut ggez::Context,
-        ggez_canvas: &mut ggez::graphics::Canvas,
-        canvas_ctx: &super::CanvasContext,
-    ) {
+    fn draw(&self, ctx: &Context, draw_list: &mut DrawList) {
         // draw select background outline
-        canvas_ctx.draw_rect(
-            ggez_canvas,
+        draw_list.rect(
             &self.get_outline_rect(),
             &self.config.background_outline_color,
         );
 
         // draw select background
-        canvas_ctx.draw_rect(
-            ggez_canvas,
-            &self.background_rect,
-            &self.config.background_color,
-        );
+        draw_list.rect(&self.background_rect, &self.config.background_color);
 
         // draw input text
-        self.input_text_handler
-            .draw(ggez_ctx, ggez_canvas, canvas_ctx);
+        self.input_text_handler.draw(ctx, draw_list);
 
         // draw selected option background
-        match self.selected_option {
-            Some(selected_option) => {
-                let vertical_padding = self.input_text_handler.config.text_vertical_padding;
-                let font_size = self.input_text_handler.config.text_font_size;
-                let input_height = self.input_text_handler.background_rect.h;
-                let input_outline = self.input_text_handler.config.background_outline_width;
-
-                let x = self.background_rect.x;
-
-                let y = self.background_rect.y - input_height / 2.0
-                    + font_size / 2.0
-                    + input_height
-                    + input_outline
-                    + vertical_padding
-                    + (selected_option as f32) * (font_size + 2.0 * vertical_padding);
-
-                let rect = Rect::new(
-                    x,
-                    y,
-                    self.background_rect.w,
-                    font_size + 2.0 * vertical_padding,
-                );
-
-                canvas_ctx.draw_rect(
-                    ggez_canvas,
-                    &rect,
-                    &self.config.selected_option_background_color,
-                );
-            }
+        if let Some(selected_option) = self.selected_option {
+            let vertical_padding = self.input_text_handler.config.text_vertical_padding;
+            let font_size = self.input_text_handler.config.text_font_size;
+            let input_height = self.input_text_handler.background_rect.h;
+            let input_outline = self.input_text_handler.config.background_outline_width;
 
-            None => {}
+            let x = self.background_rect.x;
+
+            let y = self.background_rect.y - input_height / 2.0
+                + font_size / 2.0
+                + input_height
+                + input_outline
+                + vertical_padding
+                + (selected_option as f32) * (font_size + 2.0 * vertical_padding);
+
+            let rect = Rect::new(
+                x,
+                y,
+                self.background_rect.w,
+                font_size + 2.0 * vertical_padding,
+            );
+
+            draw_list.rect(&rect, &self.config.selected_option_background_color);
         }
 
         // draw all options
-        let mut i = 0;
-        for option_index in &self.filtered_options_visible_indexes {
+        for (i, option_index) in self.filtered_options_visible_indexes.iter().enumerate() {
             let horizontal_padding = self.input_text_handler.config.text_horizontal_padding;
             let vertical_padding = self.input_text_handler.config.text_vertical_padding;
             let font_size = self.input_text_handler.config.text_font_size;
@@ -254,38 +428,35 @@ impl CanvasModeHandler for SelectHandler {
                 + 2.0 * vertical_padding
                 + (i as f32) * (font_size + 2.0 * vertical_padding);
 
-            ggez_canvas.draw(
-                &self.all_options[*option_index],
-                DrawParam::new().dest(Vec2::new(x, y)),
-            );
-
-            i += 1;
+            draw_list.text(&self.all_options[*option_index], Vec2::new(x, y));
         }
     }
 
-    fn handle_text_input(&mut self, ggez_ctx: &ggez::Context, inputted_char: char) {
+    fn handle_text_input(&mut self, ctx: &Context, inputted_char: char) {
         self.input_text_handler
-            .handle_text_input(ggez_ctx, inputted_char);
+            .handle_text_input(ctx, inputted_char);
 
         self.background_rect.w = self.input_text_handler.background_rect.w;
         self.background_rect.x = self.input_text_handler.background_rect.x;
 
         self.filter_options();
+        self.query_changed();
     }
 
-    fn handle_backspace(&mut self, ggez_ctx: &ggez::Context) {
-        self.input_text_handler.handle_backspace(ggez_ctx);
+    fn handle_backspace(&mut self, ctx: &Context) {
+        self.input_text_handler.handle_backspace(ctx);
 
         self.background_rect.w = self.input_text_handler.background_rect.w;
         self.background_rect.x = self.input_text_handler.background_rect.x;
 
         self.filter_options();
+        self.query_changed();
     }
 
-    fn handle_arrow_key(&mut self, _ggez_ctx: &ggez::Context, keycode: VirtualKeyCode) {
-        match keycode {
-            VirtualKeyCode::Up => match self.selected_option {
-                Some(selected_option) => {
+    fn handle_arrow_key(&mut self, _ctx: &Context, key: Key) {
+        match key {
+            Key::Up => {
+                if let Some(selected_option) = self.selected_option {
                     if selected_option > 0 {
                         self.selected_option = Some(selected_option - 1);
                     } else {
@@ -309,12 +480,10 @@ impl CanvasModeHandler for SelectHandler {
                         }
                     }
                 }
+            }
 
-                None => {}
-            },
-
-            VirtualKeyCode::Down => match self.selected_option {
-                Some(selected_option) => {
+            Key::Down => {
+                if let Some(selected_option) = self.selected_option {
                     if selected_option < self.filtered_options_visible_indexes.len() - 1 {
                         self.selected_option = Some(selected_option + 1);
                     } else {
@@ -334,26 +503,36 @@ impl CanvasModeHandler for SelectHandler {
                         }
                     }
                 }
-
-                None => {}
-            },
+            }
 
             _ => {}
         }
     }
 
-    fn handle_enter(&mut self, _ggez_ctx: &ggez::Context) {
-        match self.selected_option {
-            Some(selected_option) => {
-                let index = self.filtered_options_visible_indexes[selected_option];
-                let option = self.all_options_strings[index].clone();
+    fn bounds(&self) -> Rect {
+        self.get_outline_rect()
+    }
 
-                self.finished_sender
-                    .send(FinishedMessage::UserInput(option))
-                    .unwrap();
-            }
+    fn set_center(&mut self, center: Vec2) {
+        self.config.x_position = center.x;
+        self.config.y_position = center.y;
+
+        self.background_rect.x = center.x - self.background_rect.w / 2.0;
+        self.background_rect.y = center.y - self.background_rect.h / 2.0;
+
+        // the input text sits at the top of the background rect, same as in new
+        let input_height = self.input_text_handler.background_rect.h;
+        self.input_text_handler.set_center(Vec2::new(
+            center.x,
+            self.background_rect.y + input_height / 2.0,
+        ));
+    }
 
-            None => {}
+    fn handle_enter(&mut self, _ctx: &Context) {
+        if let Some(option) = self.selected_value() {
+            self.finished_sender
+                .send(FinishedMessage::UserInput(option.into()))
+                .unwrap();
         }
     }
 }
@@ -0,0 +1,33 @@
+use std::{cell::Cell, time::Duration};
+
+// Where the textbox and widgets get the time from, so anything that happens over time
+// (text appearing, timeouts, spinners) can be tested without waiting
+pub trait Clock {
+    fn time_since_start(&self) -> Duration;
+}
+
+// A clock that only moves when it's told to
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    time: Cell<Duration>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, time: Duration) {
+        self.time.set(time);
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.time.set(self.time.get() + duration);
+    }
+}
+
+impl Clock for ManualClock {
+    fn time_since_start(&self) -> Duration {
+        self.time.get()
+    }
+}
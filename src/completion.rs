@@ -0,0 +1,71 @@
+use std::{fs, path::PathBuf};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// no more than this many paths are offered at once
+pub const MAX_PATHS: usize = 200;
+
+// What Tab completes what's typed in an InputText with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Completion {
+    // the paths the last word can be completed to (see complete_path)
+    #[default]
+    Path,
+    // whatever the plugin sends back in a Completions message, after a complete event
+    Plugin,
+    // Tab does nothing
+    Off,
+}
+
+// Every file and directory the last word of text could be completed to, as the whole text with
+// the word completed. Directories end with a /, and hidden ones are only offered once a . is typed.
+// Nothing is offered for an empty word, rather than everything in the current directory
+pub fn complete_path(text: &str) -> Vec<String> {
+    let word_start = text.rfind(' ').map_or(0, |i| i + 1);
+    let word = &text[word_start..];
+    if word.is_empty() {
+        return Vec::new();
+    }
+
+    // the directory part is kept as it was typed, the rest is what names have to start with
+    let (dir, prefix) = match word.rfind('/') {
+        Some(i) => word.split_at(i + 1),
+        None => ("", word),
+    };
+
+    let dir_path = match dir {
+        "" => PathBuf::from("."),
+        dir if dir.starts_with("~/") => match dirs::home_dir() {
+            Some(home) => home.join(&dir[2..]),
+            None => return Vec::new(),
+        },
+        dir => PathBuf::from(dir),
+    };
+
+    let entries = match fs::read_dir(&dir_path) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+
+            // following symlinks, so a link to a directory completes like one
+            let is_dir = fs::metadata(entry.path()).is_ok_and(|m| m.is_dir());
+            Some(if is_dir { name + "/" } else { name })
+        })
+        .collect();
+    names.sort();
+    names.truncate(MAX_PATHS);
+
+    names
+        .into_iter()
+        .map(|name| format!("{}{}{}", &text[..word_start], dir, name))
+        .collect()
+}
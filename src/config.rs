@@ -0,0 +1,97 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{message::Protocol, window::WindowConfig};
+
+// Everything that can be set in config.toml. Command line flags take priority over it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    // pipes to read messages from and write responses to, stdin and stdout if not given
+    pub in_pipe: Option<PathBuf>,
+    pub out_pipe: Option<PathBuf>,
+    // where textbox.png and the avatar images are, found automatically if not given
+    pub res_dir: Option<PathBuf>,
+    // the name of a built-in theme or the path to a theme file
+    pub theme: Option<String>,
+    // one of off, error, warn, info, debug and trace
+    pub log_level: Option<String>,
+    // logs go here instead of stderr
+    pub log_file: Option<PathBuf>,
+    // start with the debug overlay shown (F12 toggles it)
+    pub debug_overlay: bool,
+    // joshu or jsonrpc
+    pub protocol: Protocol,
+    // where InputText histories are saved, ~/.local/share/joshu/history.json if not given
+    pub history_file: Option<PathBuf>,
+    // where the picks of Selects with a remember_key are saved, ~/.local/share/joshu/frecency.json if not given
+    pub frecency_file: Option<PathBuf>,
+    pub window: WindowConfig,
+}
+
+impl Config {
+    // ~/.config/joshu/config.toml (or wherever XDG_CONFIG_HOME points)
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("joshu").join("config.toml"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read config {}: {}", path.display(), e))?;
+
+        toml::from_str(&contents).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+    }
+
+    // Loads the config from the given path, or from the default one if it exists
+    pub fn load_or_default(path: Option<&Path>) -> Result<Self, String> {
+        match path {
+            Some(path) => Self::load(path),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => Self::load(&path),
+                _ => Ok(Self::default()),
+            },
+        }
+    }
+
+    // The first of these that has the resources in it:
+    // - res next to the executable (or in the repo, when it's run with cargo run)
+    // - joshu/res in XDG_DATA_HOME (~/.local/share) and then in every one of XDG_DATA_DIRS
+    // - res in the current directory
+    pub fn find_res_dir() -> Option<PathBuf> {
+        let mut candidates = Vec::new();
+
+        if let Some(exe_dir) = env::current_exe()
+            .ok()
+            .and_then(|e| e.parent().map(Path::to_path_buf))
+        {
+            candidates.push(exe_dir.join("res"));
+            // target/debug/joshu-core -> res
+            candidates.push(exe_dir.join("../../res"));
+            // bin/joshu-core -> share/joshu/res
+            candidates.push(exe_dir.join("../share/joshu/res"));
+        }
+
+        if let Some(data_dir) = dirs::data_dir() {
+            candidates.push(data_dir.join("joshu/res"));
+        }
+
+        let data_dirs = env::var("XDG_DATA_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or(String::from("/usr/local/share:/usr/share"));
+        for dir in data_dirs.split(':') {
+            candidates.push(Path::new(dir).join("joshu/res"));
+        }
+
+        candidates.push(PathBuf::from("res"));
+
+        candidates
+            .into_iter()
+            .find(|dir| dir.join("textbox.png").exists())
+            .and_then(|dir| dir.canonicalize().ok())
+    }
+}
@@ -0,0 +1,41 @@
+use std::rc::Rc;
+
+use crate::{
+    clock::Clock,
+    graphics::{Rect, Text, TextMeasurer},
+    screen::Screen,
+};
+
+// What the app and its widgets need from whatever is running them (a window, or a test).
+// It's made again for every call, so it's always up to date
+pub struct Context<'a> {
+    measurer: &'a dyn TextMeasurer,
+    clock: Rc<dyn Clock>,
+    screen: Screen,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(measurer: &'a dyn TextMeasurer, clock: Rc<dyn Clock>, screen: Screen) -> Self {
+        Self {
+            measurer,
+            clock,
+            screen,
+        }
+    }
+
+    // The size of the text once it's laid out, as a rect at 0, 0
+    pub fn measure(&self, text: &Text) -> Rect {
+        let size = self.measurer.measure(text);
+        Rect::new(0.0, 0.0, size.x, size.y)
+    }
+
+    // The screen that's drawn on, which sizes and positions are scaled to
+    pub fn screen(&self) -> Screen {
+        self.screen
+    }
+
+    // Anything that changes over time keeps this and reads the time from it
+    pub fn clock(&self) -> Rc<dyn Clock> {
+        self.clock.clone()
+    }
+}
@@ -0,0 +1,99 @@
+use std::{rc::Rc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::Clock,
+    graphics::{Color, DrawList, Rect},
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CountdownConfig {
+    pub bar_height: f32,
+    pub spacing: f32, // between the canvas and the bar
+    #[serde(with = "crate::theme::color")]
+    pub bar_color: Color,
+}
+
+impl Default for CountdownConfig {
+    fn default() -> Self {
+        Self {
+            bar_height: 6.0,
+            spacing: 10.0,
+            bar_color: Color::from_rgb(80, 140, 255),
+        }
+    }
+}
+
+// How long a command with a timeout has left. Time only counts while it's updated,
+// so it stops while the command is suspended
+pub struct Countdown {
+    config: CountdownConfig,
+    clock: Rc<dyn Clock>,
+    duration: Duration,
+    elapsed: Duration,
+    last_update: Duration,
+    // whether a bar showing the time left is drawn under the canvas
+    shown: bool,
+}
+
+impl Countdown {
+    pub fn new(
+        clock: Rc<dyn Clock>,
+        config: &CountdownConfig,
+        duration: Duration,
+        shown: bool,
+    ) -> Self {
+        Self {
+            config: config.clone(),
+            last_update: clock.time_since_start(),
+            clock,
+            duration,
+            elapsed: Duration::ZERO,
+            shown,
+        }
+    }
+
+    pub fn update(&mut self) {
+        let now = self.clock.time_since_start();
+        self.elapsed += now - self.last_update;
+        self.last_update = now;
+    }
+
+    // Continues counting from now, leaving out the time it wasn't updated for
+    pub fn resume(&mut self) {
+        self.last_update = self.clock.time_since_start();
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    // From 1 when it starts to 0 when it's over
+    pub fn fraction_left(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 0.0;
+        }
+
+        1.0 - (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+
+    // The bar is as wide as the canvas at first, and shrinks towards its left side
+    pub fn draw(&self, draw_list: &mut DrawList, canvas_bounds: Option<Rect>) {
+        let bounds = match canvas_bounds {
+            Some(bounds) if self.shown => bounds,
+            _ => return,
+        };
+
+        draw_list.rect(
+            &Rect::new(
+                bounds.x,
+                bounds.bottom() + self.config.spacing,
+                bounds.w * self.fraction_left(),
+                self.config.bar_height,
+            ),
+            &self.config.bar_color,
+        );
+    }
+}
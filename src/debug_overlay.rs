@@ -0,0 +1,104 @@
+use std::{collections::VecDeque, time::Duration};
+
+use crate::{
+    context::Context,
+    graphics::{Color, DrawList, Rect, Text, TextFragment, Vec2},
+    message::MessageId,
+};
+
+const FONT_SIZE: f32 = 16.0;
+const PADDING: f32 = 8.0;
+// longer input lines are cut off, so the overlay doesn't cover the whole screen
+const MAX_INPUT_CHARS: usize = 80;
+
+// What joshu is doing right now, drawn in the top left corner when it's toggled on (with F12)
+#[derive(Debug, Default)]
+pub struct DebugOverlay {
+    shown: bool,
+    // when the frames in the last second started
+    frame_times: VecDeque<Duration>,
+    last_input: String,
+}
+
+impl DebugOverlay {
+    pub fn shown(&self) -> bool {
+        self.shown
+    }
+
+    pub fn set_shown(&mut self, shown: bool) {
+        self.shown = shown;
+    }
+
+    pub fn toggle(&mut self) {
+        self.shown = !self.shown;
+    }
+
+    // Called at the start of every frame, to count them
+    pub fn frame(&mut self, now: Duration) {
+        self.frame_times.push_back(now);
+
+        let second_ago = now.saturating_sub(Duration::from_secs(1));
+        while self.frame_times.front().is_some_and(|t| *t <= second_ago) {
+            self.frame_times.pop_front();
+        }
+    }
+
+    // The frames in the last second
+    pub fn fps(&self) -> usize {
+        self.frame_times.len()
+    }
+
+    pub fn input(&mut self, line: &str) {
+        self.last_input = line.trim_end().to_string();
+    }
+
+    pub fn draw(
+        &self,
+        ctx: &Context,
+        draw_list: &mut DrawList,
+        message_id: Option<&MessageId>,
+        queue_length: usize,
+    ) {
+        if !self.shown {
+            return;
+        }
+
+        let mut last_input: String = self.last_input.chars().take(MAX_INPUT_CHARS).collect();
+        if last_input.len() < self.last_input.len() {
+            last_input.push_str("...");
+        }
+
+        let lines = [
+            format!(
+                "id: {}",
+                message_id.map_or(String::from("none"), |id| id.to_string())
+            ),
+            format!("queue: {}", queue_length),
+            format!("fps: {}", self.fps()),
+            format!("input: {}", last_input),
+        ];
+
+        let scale = ctx.screen().scale;
+        let padding = PADDING * scale;
+
+        let mut text = Text::default();
+        text.add(TextFragment {
+            text: lines.join("\n"),
+            color: Some(Color::WHITE),
+            scale: Some(FONT_SIZE * scale),
+            ..Default::default()
+        });
+
+        let text_rect = ctx.measure(&text);
+        draw_list.rect(
+            &Rect::new(
+                0.0,
+                0.0,
+                text_rect.w + 2.0 * padding,
+                text_rect.h + 2.0 * padding,
+            ),
+            &Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        draw_list.text(&text, Vec2::new(padding, padding));
+    }
+}
@@ -0,0 +1,49 @@
+use std::{fmt, io, path::PathBuf};
+
+// Everything that can go wrong while joshu starts up. Once it's running, nothing should stop it
+// (a missing avatar image is drawn blank, a closed output just stops responses from being written),
+// so the rest only fail the command they happened in
+#[derive(Debug)]
+pub enum Error {
+    // a config file that couldn't be read or parsed
+    Config(String),
+    // a theme that couldn't be read or parsed
+    Theme(String),
+    // a pipe given with --in or --out that couldn't be opened
+    Pipe { path: PathBuf, source: io::Error },
+    // an image in the resource directory that couldn't be loaded
+    Asset { path: PathBuf, reason: String },
+    // the window couldn't be created
+    Window(String),
+    // an image a command asked to show that couldn't be loaded
+    Image(String),
+    // a layout a command asked to show that doesn't make sense
+    Layout(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Config(e) => write!(f, "{}", e),
+            Error::Theme(e) => write!(f, "{}", e),
+            Error::Pipe { path, source } => {
+                write!(f, "Could not open pipe {}: {}", path.display(), source)
+            }
+            Error::Asset { path, reason } => {
+                write!(f, "Could not load {}: {}", path.display(), reason)
+            }
+            Error::Window(e) => write!(f, "Could not create the window: {}", e),
+            Error::Image(e) => write!(f, "Could not load image: {}", e),
+            Error::Layout(e) => write!(f, "Invalid layout: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Pipe { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
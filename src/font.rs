@@ -0,0 +1,193 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use ab_glyph::{Font, FontArc};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::graphics::{Text, TextFragment};
+
+struct LoadedFont {
+    path: String,
+    bytes: Vec<u8>,
+    font: FontArc,
+}
+
+// A font and its fallbacks, written in themes as a list of paths to TTF/OTF files.
+// Every character is drawn with the first font in the list that has a glyph for it,
+// and with the frontend's built-in font if none of them do (or if the list is empty)
+#[derive(Clone, Default)]
+pub struct FontChain {
+    fonts: Vec<Arc<LoadedFont>>,
+}
+
+// themes get deserialized again for every message that overrides them, so fonts are
+// only read from disk the first time
+fn loaded_fonts() -> &'static Mutex<HashMap<String, Arc<LoadedFont>>> {
+    static LOADED: OnceLock<Mutex<HashMap<String, Arc<LoadedFont>>>> = OnceLock::new();
+    LOADED.get_or_init(Default::default)
+}
+
+// The file contents of a font that was loaded by a FontChain, so a frontend can add it
+// under its path the first time it sees text using it
+pub fn font_bytes(path: &str) -> Option<Vec<u8>> {
+    let loaded = loaded_fonts().lock().unwrap();
+    loaded.get(path).map(|font| font.bytes.clone())
+}
+
+impl FontChain {
+    pub fn load(paths: &[String]) -> Result<Self, String> {
+        let mut loaded = loaded_fonts().lock().unwrap();
+
+        let mut fonts = Vec::new();
+
+        for path in paths {
+            if let Some(font) = loaded.get(path) {
+                fonts.push(font.clone());
+                continue;
+            }
+
+            let bytes =
+                std::fs::read(path).map_err(|e| format!("Could not read font {}: {}", path, e))?;
+            let font = FontArc::try_from_vec(bytes.clone())
+                .map_err(|e| format!("Could not load font {}: {}", path, e))?;
+
+            let font = Arc::new(LoadedFont {
+                path: path.clone(),
+                bytes,
+                font,
+            });
+
+            loaded.insert(path.clone(), font.clone());
+            fonts.push(font);
+        }
+
+        Ok(Self { fonts })
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        self.fonts.iter().map(|f| f.path.clone()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fonts.is_empty()
+    }
+
+    // This chain, falling back to the other one's fonts after its own
+    pub fn then(&self, other: &FontChain) -> FontChain {
+        let mut fonts = self.fonts.clone();
+        for font in &other.fonts {
+            if !fonts.iter().any(|f| f.path == font.path) {
+                fonts.push(font.clone());
+            }
+        }
+
+        Self { fonts }
+    }
+
+    fn font_for(&self, ch: char) -> Option<String> {
+        self.fonts
+            .iter()
+            .find(|f| f.font.glyph_id(ch).0 != 0)
+            .map(|f| f.path.clone())
+    }
+
+    // Sets the font of the fragment based on its first character (for fragments that are a single character)
+    pub fn fragment(&self, fragment: TextFragment) -> TextFragment {
+        let font = fragment
+            .text
+            .chars()
+            .next()
+            .and_then(|ch| self.font_for(ch));
+
+        TextFragment { font, ..fragment }
+    }
+
+    // Splits the fragment into runs of characters that use the same font
+    pub fn text(&self, fragment: TextFragment) -> Text {
+        let mut text = Text::default();
+
+        if self.is_empty() {
+            text.add(fragment);
+            return text;
+        }
+
+        let mut run = String::new();
+        let mut run_font = None;
+
+        for ch in fragment.text.chars() {
+            let font = self.font_for(ch);
+
+            if font != run_font && !run.is_empty() {
+                text.add(TextFragment {
+                    text: std::mem::take(&mut run),
+                    font: run_font,
+                    ..fragment.clone()
+                });
+            }
+
+            run.push(ch);
+            run_font = font;
+        }
+
+        text.add(TextFragment {
+            text: run,
+            font: run_font,
+            ..fragment
+        });
+
+        text
+    }
+}
+
+impl PartialEq for FontChain {
+    fn eq(&self, other: &Self) -> bool {
+        self.paths() == other.paths()
+    }
+}
+
+impl fmt::Debug for FontChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.paths()).finish()
+    }
+}
+
+impl Serialize for FontChain {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.paths().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FontChain {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let paths = Vec::<String>::deserialize(deserializer)?;
+        Self::load(&paths).map_err(D::Error::custom)
+    }
+}
+
+// The fonts used by each kind of text, the default ones are used for all other text
+// and as fallbacks for the rest
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FontsConfig {
+    pub default: FontChain,
+    pub textbox: FontChain,
+    pub input: FontChain,   // inputted text and placeholders
+    pub options: FontChain, // options of selects and dropdowns
+}
+
+impl FontsConfig {
+    pub fn textbox(&self) -> FontChain {
+        self.textbox.then(&self.default)
+    }
+
+    pub fn input(&self) -> FontChain {
+        self.input.then(&self.default)
+    }
+
+    pub fn options(&self) -> FontChain {
+        self.options.then(&self.default)
+    }
+}
@@ -0,0 +1,118 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::store;
+
+// the options picked longest ago are forgotten once a key has more than this
+pub const MAX_OPTIONS: usize = 1000;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Picks {
+    count: u32,
+    // in seconds since the unix epoch
+    last_picked: u64,
+}
+
+// How often and how recently the options of Selects with a remember_key were picked, so the ones
+// picked a lot lately can be shown first. It's saved like the InputText history (see History)
+#[derive(Debug, Default)]
+pub struct Frecency {
+    // None keeps it in memory only (like in tests)
+    path: Option<PathBuf>,
+    picks: HashMap<String, HashMap<String, Picks>>,
+}
+
+impl Frecency {
+    // ~/.local/share/joshu/frecency.json (or wherever XDG_DATA_HOME points)
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("joshu").join("frecency.json"))
+    }
+
+    pub fn load(path: &Path) -> Self {
+        Self {
+            path: Some(path.to_path_buf()),
+            picks: store::load(path, "frecency stats"),
+        }
+    }
+
+    // Seconds since the unix epoch, which is what now is everywhere here
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs())
+    }
+
+    pub fn record(&mut self, key: &str, option: &str, now: u64) {
+        let picks = self.picks.entry(key.to_string()).or_default();
+
+        let entry = picks.entry(option.to_string()).or_insert(Picks {
+            count: 0,
+            last_picked: now,
+        });
+        entry.count += 1;
+        entry.last_picked = now;
+
+        if picks.len() > MAX_OPTIONS {
+            let oldest = picks
+                .iter()
+                .min_by_key(|(_, p)| p.last_picked)
+                .map(|(option, _)| option.clone());
+            if let Some(oldest) = oldest {
+                picks.remove(&oldest);
+            }
+        }
+
+        self.save();
+    }
+
+    // Every option picked before under the key, with how much it should be boosted. Picks count
+    // for less the longer ago the option was last picked
+    pub fn scores(&self, key: &str, now: u64) -> HashMap<String, f32> {
+        let picks = match self.picks.get(key) {
+            Some(picks) => picks,
+            None => return HashMap::new(),
+        };
+
+        picks
+            .iter()
+            .map(|(option, p)| {
+                let weight = match now.saturating_sub(p.last_picked) {
+                    age if age < HOUR => 4.0,
+                    age if age < DAY => 2.0,
+                    age if age < 7 * DAY => 1.0,
+                    age if age < 30 * DAY => 0.5,
+                    _ => 0.25,
+                };
+
+                (option.clone(), p.count as f32 * weight)
+            })
+            .collect()
+    }
+
+    // Forgets the picks under the key (or under every key), returning whether there were any
+    pub fn reset(&mut self, key: Option<&str>) -> bool {
+        let had_picks = match key {
+            Some(key) => self.picks.remove(key).is_some(),
+            None => !std::mem::take(&mut self.picks).is_empty(),
+        };
+
+        if had_picks {
+            self.save();
+        }
+        had_picks
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            store::save(path, &self.picks, "frecency stats");
+        }
+    }
+}
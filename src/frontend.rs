@@ -0,0 +1,300 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    io::Write,
+    rc::Rc,
+    sync::mpsc::Receiver,
+    time::Duration,
+};
+
+use ggez::{
+    event::EventHandler,
+    graphics::{self, Canvas, DrawMode, DrawParam, FontData, GraphicsContext, ImageFormat, Mesh},
+    input::keyboard::{KeyInput, KeyMods},
+    timer::TimeContext,
+    winit::event::VirtualKeyCode,
+    GameError, GameResult,
+};
+
+use crate::{
+    app::App,
+    assets::Assets,
+    clock::Clock,
+    context::Context,
+    font,
+    graphics::{Color, DrawCommand, DrawList, Rect, Text, TextMeasurer, Vec2},
+    input::{InputEvent, Key, Shortcut},
+    screen::Screen,
+    theme::Theme,
+};
+
+// Runs an App in a ggez window, turning ggez's events into the app's and drawing what it asks for
+pub struct Frontend {
+    app: App,
+    // whether the fonts text used could be added to ggez, by path (fonts are added the first
+    // time text uses them, and ones that couldn't be aren't tried again)
+    fonts: HashMap<String, bool>,
+    // images that were uploaded, by Image::id
+    images: HashMap<u64, graphics::Image>,
+    // this mesh is used to draw any rectangle on screen
+    // at first I had a separate rect mesh like this for every rectangle, but
+    // it turns out you can just reuse a single one, so that's what this is for
+    rect_mesh: Mesh,
+    clock: Rc<GgezClock>,
+    screen: Screen,
+}
+
+impl Frontend {
+    pub fn new(
+        ggez_ctx: &mut ggez::Context,
+        input_receiver: Receiver<String>,
+        output: Box<dyn Write>,
+        assets: Assets,
+        theme: Theme,
+        screen: Screen,
+    ) -> Self {
+        let rect_mesh = Mesh::new_rectangle(
+            &ggez_ctx.gfx,
+            DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, 1.0, 1.0),
+            graphics::Color::WHITE,
+        )
+        .unwrap();
+
+        let clock = Rc::new(GgezClock::default());
+        clock.sync(&ggez_ctx.time);
+
+        let mut fonts = HashMap::new();
+        let measurer = GgezMeasurer {
+            gfx: RefCell::new(&mut ggez_ctx.gfx),
+            fonts: RefCell::new(&mut fonts),
+        };
+        let ctx = Context::new(&measurer, clock.clone(), screen);
+        let app = App::new(&ctx, input_receiver, output, assets, theme);
+
+        Self {
+            app,
+            fonts,
+            images: HashMap::new(),
+            rect_mesh,
+            clock,
+            screen,
+        }
+    }
+
+    pub fn app_mut(&mut self) -> &mut App {
+        &mut self.app
+    }
+
+    // Calls f with a core context that measures text with ggez
+    fn with_context<R>(
+        &mut self,
+        ggez_ctx: &mut ggez::Context,
+        f: impl FnOnce(&mut App, &Context) -> R,
+    ) -> R {
+        self.clock.sync(&ggez_ctx.time);
+        let measurer = GgezMeasurer {
+            gfx: RefCell::new(&mut ggez_ctx.gfx),
+            fonts: RefCell::new(&mut self.fonts),
+        };
+
+        let ctx = Context::new(&measurer, self.clock.clone(), self.screen);
+        let result = f(&mut self.app, &ctx);
+
+        if self.app.quit_requested() {
+            ggez_ctx.request_quit();
+        }
+
+        result
+    }
+
+    fn handle_input(&mut self, ggez_ctx: &mut ggez::Context, event: InputEvent) {
+        self.with_context(ggez_ctx, |app, ctx| app.handle_input(ctx, event));
+    }
+}
+
+impl EventHandler for Frontend {
+    fn update(&mut self, ggez_ctx: &mut ggez::Context) -> GameResult {
+        self.with_context(ggez_ctx, |app, ctx| app.update(ctx));
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ggez_ctx: &mut ggez::Context) -> GameResult {
+        let mut draw_list = DrawList::default();
+        self.with_context(ggez_ctx, |app, ctx| app.draw(ctx, &mut draw_list));
+
+        let mut canvas = Canvas::from_frame(ggez_ctx, graphics::Color::new(0.0, 0.0, 0.0, 0.0));
+        let mut drawn_images = HashSet::new();
+
+        for command in draw_list.commands() {
+            match command {
+                DrawCommand::Rect { rect, color } => canvas.draw(
+                    &self.rect_mesh,
+                    DrawParam::new()
+                        .dest_rect(ggez_rect(*rect))
+                        .color(ggez_color(*color)),
+                ),
+
+                DrawCommand::Text { text, dest } => {
+                    add_fonts(&mut ggez_ctx.gfx, &mut self.fonts, text);
+                    canvas.draw(&ggez_text(text, &self.fonts), DrawParam::new().dest(*dest));
+                }
+
+                DrawCommand::Image { image, dest, scale } => {
+                    if image.is_blank() {
+                        continue;
+                    }
+
+                    let ggez_image = self.images.entry(image.id()).or_insert_with(|| {
+                        graphics::Image::from_pixels(
+                            ggez_ctx,
+                            image.pixels(),
+                            ImageFormat::Rgba8UnormSrgb,
+                            image.width(),
+                            image.height(),
+                        )
+                    });
+                    drawn_images.insert(image.id());
+
+                    canvas.draw(ggez_image, DrawParam::new().dest(*dest).scale(*scale));
+                }
+            }
+        }
+
+        // images that aren't shown anymore (like ones from finished commands) don't need to stay uploaded
+        self.images.retain(|id, _| drawn_images.contains(id));
+
+        canvas.finish(ggez_ctx)
+    }
+
+    fn key_down_event(
+        &mut self,
+        ggez_ctx: &mut ggez::Context,
+        input: KeyInput,
+        _repeated: bool,
+    ) -> Result<(), GameError> {
+        let key = match input.keycode {
+            Some(VirtualKeyCode::Back) => Key::Backspace,
+            Some(VirtualKeyCode::Return) => Key::Enter,
+            Some(VirtualKeyCode::Tab) => Key::Tab,
+            Some(VirtualKeyCode::Escape) => Key::Escape,
+            Some(VirtualKeyCode::Left) => Key::Left,
+            Some(VirtualKeyCode::Right) => Key::Right,
+            Some(VirtualKeyCode::Up) => Key::Up,
+            Some(VirtualKeyCode::Down) => Key::Down,
+            Some(VirtualKeyCode::F12) => Key::F12,
+            Some(VirtualKeyCode::R) if input.mods.contains(KeyMods::CTRL) => {
+                let shortcut = InputEvent::Shortcut(Shortcut::SearchHistory);
+                self.handle_input(ggez_ctx, shortcut);
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+
+        self.handle_input(ggez_ctx, InputEvent::Key(key));
+
+        Ok(())
+    }
+
+    fn text_input_event(
+        &mut self,
+        ggez_ctx: &mut ggez::Context,
+        ch: char,
+    ) -> Result<(), GameError> {
+        self.handle_input(ggez_ctx, InputEvent::Text(ch));
+
+        Ok(())
+    }
+}
+
+// The time according to ggez, synced before every event so it stays the same for a whole frame
+#[derive(Default)]
+struct GgezClock {
+    time: Cell<Duration>,
+}
+
+impl GgezClock {
+    fn sync(&self, time: &TimeContext) {
+        self.time.set(time.time_since_start());
+    }
+}
+
+impl Clock for GgezClock {
+    fn time_since_start(&self) -> Duration {
+        self.time.get()
+    }
+}
+
+struct GgezMeasurer<'a> {
+    // measuring can add fonts, and TextMeasurer only gets &self
+    gfx: RefCell<&'a mut GraphicsContext>,
+    fonts: RefCell<&'a mut HashMap<String, bool>>,
+}
+
+impl TextMeasurer for GgezMeasurer<'_> {
+    fn measure(&self, text: &Text) -> Vec2 {
+        let mut gfx = self.gfx.borrow_mut();
+        let mut fonts = self.fonts.borrow_mut();
+        add_fonts(&mut gfx, &mut fonts, text);
+
+        match ggez_text(text, &fonts).measure(&**gfx) {
+            Ok(size) => Vec2::new(size.x, size.y),
+            Err(e) => {
+                log::warn!("Could not measure text {:?}: {}", text.contents(), e);
+                Vec2::ZERO
+            }
+        }
+    }
+}
+
+// Adds the fonts the text uses to ggez, named after their paths, if they weren't tried yet
+fn add_fonts(gfx: &mut GraphicsContext, fonts: &mut HashMap<String, bool>, text: &Text) {
+    for fragment in text.fragments() {
+        let path = match &fragment.font {
+            Some(path) if !fonts.contains_key(path) => path,
+            _ => continue,
+        };
+
+        let added = match font::font_bytes(path).map(FontData::from_vec) {
+            Some(Ok(data)) => {
+                gfx.add_font(path, data);
+                true
+            }
+
+            _ => {
+                log::warn!("Could not add font {}", path);
+                false
+            }
+        };
+        fonts.insert(path.clone(), added);
+    }
+}
+
+// Fonts that couldn't be added are left out, so ggez's built-in one is used instead
+fn ggez_text(text: &Text, fonts: &HashMap<String, bool>) -> graphics::Text {
+    let mut ggez_text = graphics::Text::default();
+
+    for fragment in text.fragments() {
+        ggez_text.add(graphics::TextFragment {
+            text: fragment.text.clone(),
+            font: fragment
+                .font
+                .clone()
+                .filter(|font| fonts.get(font) == Some(&true)),
+            scale: fragment.scale.map(graphics::PxScale::from),
+            color: fragment.color.map(ggez_color),
+        });
+    }
+
+    ggez_text.set_bounds(text.bounds());
+    ggez_text
+}
+
+fn ggez_color(color: Color) -> graphics::Color {
+    graphics::Color::new(color.r, color.g, color.b, color.a)
+}
+
+fn ggez_rect(rect: Rect) -> graphics::Rect {
+    graphics::Rect::new(rect.x, rect.y, rect.w, rect.h)
+}
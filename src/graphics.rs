@@ -0,0 +1,324 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+pub use glam::Vec2;
+
+// These mirror the ggez types they replace, so the widgets don't need a window (or ggez at all)
+// to work out what to show. The ggez frontend turns them back into ggez's own when drawing
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl Rect {
+    pub const fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Self { x, y, w, h }
+    }
+
+    pub fn right(&self) -> f32 {
+        self.x + self.w
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.y + self.h
+    }
+
+    // The smallest rect that contains both of them
+    pub fn combine_with(self, other: Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+
+        Rect::new(
+            x,
+            y,
+            self.right().max(other.right()) - x,
+            self.bottom().max(other.bottom()) - y,
+        )
+    }
+}
+
+// Channels go from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0, 1.0);
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::from_rgba(r, g, b, 255)
+    }
+
+    pub fn from_rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self::new(
+            r as f32 / 255.0,
+            g as f32 / 255.0,
+            b as f32 / 255.0,
+            a as f32 / 255.0,
+        )
+    }
+
+    pub fn to_rgba(self) -> (u8, u8, u8, u8) {
+        (
+            (self.r * 255.0).round() as u8,
+            (self.g * 255.0).round() as u8,
+            (self.b * 255.0).round() as u8,
+            (self.a * 255.0).round() as u8,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextFragment {
+    pub text: String,
+    pub color: Option<Color>,
+    // the font size in pixels
+    pub scale: Option<f32>,
+    // the path of the font (see FontChain), the frontend's default font if not given
+    pub font: Option<String>,
+}
+
+impl From<&str> for TextFragment {
+    fn from(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Text {
+    fragments: Vec<TextFragment>,
+    // the text wraps when it gets wider than bounds.x
+    bounds: Vec2,
+}
+
+impl Default for Text {
+    fn default() -> Self {
+        Self {
+            fragments: vec![],
+            bounds: Vec2::splat(f32::INFINITY),
+        }
+    }
+}
+
+impl Text {
+    pub fn new(fragment: impl Into<TextFragment>) -> Self {
+        let mut text = Self::default();
+        text.add(fragment);
+        text
+    }
+
+    pub fn add(&mut self, fragment: impl Into<TextFragment>) -> &mut Self {
+        self.fragments.push(fragment.into());
+        self
+    }
+
+    pub fn fragments(&self) -> &[TextFragment] {
+        &self.fragments
+    }
+
+    pub fn contents(&self) -> String {
+        self.fragments.iter().map(|f| f.text.as_str()).collect()
+    }
+
+    pub fn set_bounds(&mut self, bounds: Vec2) -> &mut Self {
+        self.bounds = bounds;
+        self
+    }
+
+    pub fn bounds(&self) -> Vec2 {
+        self.bounds
+    }
+}
+
+// Works out how big text is once it's laid out, which only whatever draws it really knows
+pub trait TextMeasurer {
+    fn measure(&self, text: &Text) -> Vec2;
+}
+
+// Pretends every character is as wide as in ggez's built-in font (which is monospace), for
+// running without a window. Lines wrap at any character instead of at spaces
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MonospaceMeasurer;
+
+impl MonospaceMeasurer {
+    pub const DEFAULT_FONT_SIZE: f32 = 16.0;
+    // the advance of a glyph in Liberation Mono, relative to the font size
+    pub const CHAR_WIDTH: f32 = 0.6;
+}
+
+impl TextMeasurer for MonospaceMeasurer {
+    fn measure(&self, text: &Text) -> Vec2 {
+        let max_width = text.bounds().x;
+
+        let mut width: f32 = 0.0;
+        let mut height = 0.0;
+        let mut line_width = 0.0;
+        let mut line_height: f32 = 0.0;
+
+        for fragment in text.fragments() {
+            let size = fragment.scale.unwrap_or(Self::DEFAULT_FONT_SIZE);
+
+            for ch in fragment.text.chars() {
+                let char_width = if ch == '\n' {
+                    0.0
+                } else {
+                    size * Self::CHAR_WIDTH
+                };
+
+                if ch == '\n' || line_width + char_width > max_width {
+                    width = width.max(line_width);
+                    height += line_height;
+                    line_width = 0.0;
+                    line_height = 0.0;
+                }
+
+                line_width += char_width;
+                line_height = line_height.max(size);
+            }
+        }
+
+        Vec2::new(width.max(line_width), height + line_height)
+    }
+}
+
+static NEXT_IMAGE_ID: AtomicU64 = AtomicU64::new(0);
+
+// A decoded image (RGBA, 8 bits per channel). Clones share the pixels and the id, which the
+// frontend uses to only upload every image once
+#[derive(Debug, Clone)]
+pub struct Image {
+    id: u64,
+    width: u32,
+    height: u32,
+    pixels: Arc<Vec<u8>>,
+}
+
+impl Image {
+    // Decodes an encoded image (PNG, JPEG, GIF, ...)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let decoded = image::load_from_memory(bytes)
+            .map_err(|e| format!("failed to load image: {}", e))?
+            .to_rgba8();
+
+        Ok(Self::from_pixels(
+            decoded.width(),
+            decoded.height(),
+            decoded.into_raw(),
+        ))
+    }
+
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        Self {
+            id: NEXT_IMAGE_ID.fetch_add(1, Ordering::Relaxed),
+            width,
+            height,
+            pixels: Arc::new(pixels),
+        }
+    }
+
+    // An image with a size but no pixels, which draws as nothing. Used when there's nothing
+    // to draw it on anyway
+    pub fn blank(width: u32, height: u32) -> Self {
+        Self::from_pixels(width, height, vec![])
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn is_blank(&self) -> bool {
+        self.pixels.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DrawCommand {
+    Rect {
+        rect: Rect,
+        color: Color,
+    },
+    Text {
+        text: Text,
+        dest: Vec2,
+    },
+    Image {
+        image: Image,
+        dest: Vec2,
+        scale: Vec2,
+    },
+}
+
+// Everything that's drawn in a frame, in order (later things are drawn on top)
+#[derive(Debug, Clone, Default)]
+pub struct DrawList {
+    commands: Vec<DrawCommand>,
+}
+
+impl DrawList {
+    pub fn rect(&mut self, rect: &Rect, color: &Color) {
+        self.commands.push(DrawCommand::Rect {
+            rect: *rect,
+            color: *color,
+        });
+    }
+
+    pub fn text(&mut self, text: &Text, dest: Vec2) {
+        self.commands.push(DrawCommand::Text {
+            text: text.clone(),
+            dest,
+        });
+    }
+
+    pub fn image(&mut self, image: &Image, dest: Vec2, scale: Vec2) {
+        self.commands.push(DrawCommand::Image {
+            image: image.clone(),
+            dest,
+            scale,
+        });
+    }
+
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+
+    // The contents of every text that's drawn, in order
+    pub fn texts(&self) -> Vec<String> {
+        self.commands
+            .iter()
+            .filter_map(|c| match c {
+                DrawCommand::Text { text, .. } => Some(text.contents()),
+                _ => None,
+            })
+            .collect()
+    }
+}
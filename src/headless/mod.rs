@@ -0,0 +1,235 @@
+use std::{
+    io::{self, Write},
+    rc::Rc,
+    sync::{
+        mpsc::{channel, Sender},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{
+    app::App,
+    assets::Assets,
+    clock::{Clock, ManualClock},
+    context::Context,
+    frecency::Frecency,
+    graphics::{DrawList, MonospaceMeasurer},
+    history::History,
+    input::{InputEvent, Key, Shortcut},
+    message::{Message, Protocol},
+    screen::Screen,
+    theme::Theme,
+};
+
+pub use self::script::run_script;
+
+mod script;
+
+// How much time passes in a frame when time is advanced
+pub const FRAME: Duration = Duration::from_millis(16);
+
+// Runs an App without a window, for tests and for embedding it in something that draws on its own.
+// Messages and keypresses go in, responses and draw lists come out, and time only passes
+// when it's advanced
+pub struct Headless {
+    app: App,
+    // None once the input was closed
+    sender: Option<Sender<String>>,
+    output: SharedOutput,
+    // how much of the output was already returned by responses
+    read: usize,
+    measurer: MonospaceMeasurer,
+    clock: Rc<ManualClock>,
+    screen: Screen,
+    protocol: Protocol,
+}
+
+// The app writes its responses here, and they're read back from the other clone
+#[derive(Clone, Default)]
+struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Default for Headless {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Headless {
+    pub fn new() -> Self {
+        Self::with_theme(Theme::light())
+    }
+
+    pub fn with_theme(theme: Theme) -> Self {
+        Self::with_screen(theme, Screen::default())
+    }
+
+    // Runs as if it was drawn on the given screen (1080p otherwise)
+    pub fn with_screen(theme: Theme, screen: Screen) -> Self {
+        let (sender, receiver) = channel();
+        let output = SharedOutput::default();
+        let measurer = MonospaceMeasurer;
+        let clock = Rc::new(ManualClock::new());
+
+        let app = App::new(
+            &Context::new(&measurer, clock.clone(), screen),
+            receiver,
+            Box::new(output.clone()),
+            Assets::blank(),
+            theme,
+        );
+
+        Self {
+            app,
+            sender: Some(sender),
+            output,
+            read: 0,
+            measurer,
+            clock,
+            screen,
+            protocol: Protocol::default(),
+        }
+    }
+
+    pub fn app(&self) -> &App {
+        &self.app
+    }
+
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol = protocol;
+        self.app.set_protocol(protocol);
+    }
+
+    pub fn set_history(&mut self, history: History) {
+        self.app.set_history(history);
+    }
+
+    pub fn set_frecency(&mut self, frecency: Frecency) {
+        self.app.set_frecency(frecency);
+    }
+
+    pub fn time_since_start(&self) -> Duration {
+        self.clock.time_since_start()
+    }
+
+    // Sends a message like the ones read from the input pipe, and runs a frame so it's handled.
+    // Invalid messages are an error here (with the JSON-RPC protocol, anything that isn't json),
+    // use send_line to send them anyway
+    pub fn send(&mut self, message: &str) -> Result<(), String> {
+        self.send_later(message)?;
+        self.frame();
+
+        Ok(())
+    }
+
+    // Sends a message like send, but without running a frame, so it's read in the same frame as
+    // whatever happens next (like a key press, which can happen in between frames in a window)
+    pub fn send_later(&mut self, message: &str) -> Result<(), String> {
+        match self.protocol {
+            Protocol::Joshu => serde_json::from_str::<Message>(message).map(|_| ()),
+            Protocol::JsonRpc => serde_json::from_str::<serde_json::Value>(message).map(|_| ()),
+        }
+        .map_err(|e| format!("Invalid message {}: {}", message, e))?;
+
+        let sender = self.sender.as_ref().ok_or("The input was closed")?;
+        sender.send(message.to_string()).unwrap();
+
+        Ok(())
+    }
+
+    // Sends a line as if it was read from the input pipe, and runs a frame
+    pub fn send_line(&mut self, line: &str) -> Result<(), String> {
+        let sender = self.sender.as_ref().ok_or("The input was closed")?;
+        sender.send(line.to_string()).unwrap();
+        self.frame();
+
+        Ok(())
+    }
+
+    // Like stdin reaching its end: what was already sent is still handled, then joshu quits
+    pub fn close_input(&mut self) {
+        self.sender = None;
+        self.frame();
+    }
+
+    // Handles the event and runs a frame, so whatever it finished gets responded to
+    pub fn input(&mut self, event: InputEvent) {
+        let ctx = Context::new(&self.measurer, self.clock.clone(), self.screen);
+        self.app.handle_input(&ctx, event);
+        self.frame();
+    }
+
+    pub fn press(&mut self, key: Key) {
+        self.input(InputEvent::Key(key));
+    }
+
+    pub fn shortcut(&mut self, shortcut: Shortcut) {
+        self.input(InputEvent::Shortcut(shortcut));
+    }
+
+    pub fn type_text(&mut self, text: &str) {
+        for ch in text.chars() {
+            self.input(InputEvent::Text(ch));
+        }
+    }
+
+    // Runs a single update without any time passing
+    pub fn frame(&mut self) {
+        let ctx = Context::new(&self.measurer, self.clock.clone(), self.screen);
+        self.app.update(&ctx);
+    }
+
+    // Runs a frame for every FRAME that fits in the duration (and one for the rest)
+    pub fn advance(&mut self, duration: Duration) {
+        let end = self.time_since_start() + duration;
+
+        while self.time_since_start() < end {
+            self.clock.set((self.time_since_start() + FRAME).min(end));
+            self.frame();
+        }
+    }
+
+    pub fn draw(&self) -> DrawList {
+        let mut draw_list = DrawList::default();
+        let ctx = Context::new(&self.measurer, self.clock.clone(), self.screen);
+        self.app.draw(&ctx, &mut draw_list);
+
+        draw_list
+    }
+
+    pub fn quit_requested(&self) -> bool {
+        self.app.quit_requested()
+    }
+
+    // Everything the app wrote, one response per line
+    pub fn output(&self) -> String {
+        String::from_utf8_lossy(&self.output.0.lock().unwrap()).into_owned()
+    }
+
+    // The lines written since the last time this (or responses) was called
+    pub fn lines(&mut self) -> Vec<String> {
+        let output = self.output();
+        let new = &output[self.read..];
+        self.read = output.len();
+
+        new.lines().map(String::from).collect()
+    }
+
+    // Same as lines, but parsed
+    pub fn responses(&mut self) -> Vec<serde_json::Value> {
+        self.lines()
+            .iter()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+}
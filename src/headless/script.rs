@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use crate::{
+    input::{InputEvent, Key, Shortcut},
+    message::Protocol,
+};
+
+use super::Headless;
+
+// Runs a test script against a new Headless, returning the first thing that didn't go as expected.
+// Every line of a script is one step, empty lines and lines starting with # are skipped:
+//
+// > {"id": "1", "canvas_mode": "InputText"}    sends a message
+// >> {"id": "2", "interrupt": true}            sends a message that's only read in the next step's frame
+// raw {"id": 1, "kind": "Nope"}                sends the line as it is, even if it isn't a valid message
+// type hello world                             types the rest of the line
+// type " "                                     types the json string (for text with spaces at the ends)
+// press Enter                                  presses keys (Backspace, Enter, Tab, Escape, Left, Right, Up, Down, F12, Ctrl+R)
+// wait 500                                     moves time forward by this many milliseconds
+// frame                                        runs a single frame without moving time forward
+// < {"id":"1","data":"hello world"}            checks the next response, which has to be exactly this line
+// quit                                         checks that joshu wants to quit
+// close                                        closes the input, like stdin reaching its end
+// protocol jsonrpc                             switches to another protocol (joshu or jsonrpc)
+//
+// Once the script is done, every response has to have been checked
+pub fn run_script(script: &str) -> Result<(), String> {
+    let mut joshu = Headless::new();
+    // responses that were written but not checked yet
+    let mut unchecked = Vec::<String>::new();
+
+    for (i, line) in script.lines().enumerate() {
+        let line_number = i + 1;
+        let error = |e: String| format!("line {}: {}", line_number, e);
+
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let (step, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+        match step {
+            ">" => joshu.send(rest).map_err(error)?,
+
+            ">>" => joshu.send_later(rest).map_err(error)?,
+
+            "raw" => joshu.send_line(rest).map_err(error)?,
+
+            "type" if rest.starts_with('"') => {
+                let text = serde_json::from_str::<String>(rest)
+                    .map_err(|e| error(format!("Invalid text {}: {}", rest, e)))?;
+                joshu.type_text(&text);
+            }
+
+            "type" => joshu.type_text(rest),
+
+            "press" => {
+                for name in rest.split_whitespace() {
+                    let key =
+                        parse_key(name).ok_or_else(|| error(format!("Unknown key {}", name)))?;
+                    joshu.input(key);
+                }
+            }
+
+            "wait" => {
+                let ms = rest
+                    .trim()
+                    .parse()
+                    .map_err(|_| error(format!("Invalid wait {}", rest)))?;
+                joshu.advance(Duration::from_millis(ms));
+            }
+
+            "frame" => joshu.frame(),
+
+            "close" => joshu.close_input(),
+
+            "protocol" => {
+                let protocol = serde_json::from_value::<Protocol>(rest.trim().into())
+                    .map_err(|_| error(format!("Unknown protocol {}", rest)))?;
+                joshu.set_protocol(protocol);
+            }
+
+            "<" => {
+                unchecked.extend(joshu.lines());
+
+                if unchecked.is_empty() {
+                    return Err(error(format!("Expected {} but nothing was written", rest)));
+                }
+
+                let response = unchecked.remove(0);
+                if response != rest {
+                    return Err(error(format!("Expected {} but got {}", rest, response)));
+                }
+            }
+
+            "quit" => {
+                if !joshu.quit_requested() {
+                    return Err(error(String::from("Expected joshu to quit")));
+                }
+            }
+
+            _ => return Err(error(format!("Unknown step {}", step))),
+        }
+    }
+
+    unchecked.extend(joshu.lines());
+    match unchecked.first() {
+        Some(response) => Err(format!("Unexpected response {}", response)),
+        None => Ok(()),
+    }
+}
+
+fn parse_key(name: &str) -> Option<InputEvent> {
+    if name == "Ctrl+R" {
+        return Some(InputEvent::Shortcut(Shortcut::SearchHistory));
+    }
+
+    Some(InputEvent::Key(match name {
+        "Backspace" => Key::Backspace,
+        "Enter" => Key::Enter,
+        "Tab" => Key::Tab,
+        "Escape" => Key::Escape,
+        "Left" => Key::Left,
+        "Right" => Key::Right,
+        "Up" => Key::Up,
+        "Down" => Key::Down,
+        "F12" => Key::F12,
+        _ => return None,
+    }))
+}
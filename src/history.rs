@@ -0,0 +1,58 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::store;
+
+// older entries are forgotten once a key has more than this
+pub const MAX_ENTRIES: usize = 500;
+
+// What was submitted in InputTexts with a history_key, oldest first for every key.
+// It's saved to a json file after every change, so it's there the next time joshu starts
+#[derive(Debug, Default)]
+pub struct History {
+    // None keeps it in memory only (like in tests)
+    path: Option<PathBuf>,
+    entries: HashMap<String, Vec<String>>,
+}
+
+impl History {
+    // ~/.local/share/joshu/history.json (or wherever XDG_DATA_HOME points)
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("joshu").join("history.json"))
+    }
+
+    pub fn load(path: &Path) -> Self {
+        Self {
+            path: Some(path.to_path_buf()),
+            entries: store::load(path, "history"),
+        }
+    }
+
+    pub fn entries(&self, key: &str) -> &[String] {
+        self.entries.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    // Adds a submitted value as the newest entry, moving it there if it was already in the history
+    pub fn add(&mut self, key: &str, value: &str) {
+        if value.is_empty() {
+            return;
+        }
+
+        let entries = self.entries.entry(key.to_string()).or_default();
+        entries.retain(|entry| entry != value);
+        entries.push(value.to_string());
+
+        let too_many = entries.len().saturating_sub(MAX_ENTRIES);
+        entries.drain(..too_many);
+
+        self.save();
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            store::save(path, &self.entries, "history");
+        }
+    }
+}
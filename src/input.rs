@@ -0,0 +1,29 @@
+// The keys that do something, whatever the frontend calls them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Backspace,
+    Enter,
+    Tab,
+    Escape,
+    Left,
+    Right,
+    Up,
+    Down,
+    // toggles the debug overlay
+    F12,
+}
+
+// Key combinations, which do something of their own instead of what the keys in them would do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortcut {
+    // Ctrl+R, searches back through an InputText's history
+    SearchHistory,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    Key(Key),
+    Shortcut(Shortcut),
+    // a typed character (keys like enter and backspace also send control characters, which are ignored)
+    Text(char),
+}
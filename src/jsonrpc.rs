@@ -0,0 +1,290 @@
+use serde_json::{json, Value};
+
+use crate::{
+    canvas::CanvasMode,
+    completion::Completion,
+    message::{Message, MessageId, MessageIds, MessageKind, Response},
+    schema,
+};
+
+// The error codes from the JSON-RPC 2.0 spec
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+// and the ones joshu uses (the spec leaves -32000 to -32099 to servers)
+pub const COMMAND_ERROR: i64 = -32000;
+pub const TIMED_OUT: i64 = -32001;
+
+pub const METHODS: [&str; 5] = ["show", "prompt", "select", "describe", "quit"];
+
+// What a request asks joshu to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum Call {
+    // show, prompt and select are all commands, they only differ in the canvas mode they default to.
+    // describe is a Describe message
+    Message(Box<Message>),
+    Quit(MessageIds),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub id: Option<MessageId>,
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(id: Option<MessageId>, code: i64, message: impl Into<String>) -> Self {
+        Self {
+            id,
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": self.id,
+            "error": { "code": self.code, "message": self.message },
+        })
+    }
+}
+
+// The responses to a batch are sent together, once every request in it that has an id was responded to
+struct Batch {
+    waiting_for: Vec<MessageId>,
+    responses: Vec<Value>,
+}
+
+// Turns lines of JSON-RPC requests into calls, and responses into JSON-RPC responses.
+// Requests without an id are notifications, which never get a response
+#[derive(Default)]
+pub struct JsonRpc {
+    batches: Vec<Batch>,
+}
+
+impl JsonRpc {
+    pub fn parse(&mut self, line: &str) -> Vec<Result<Call, RpcError>> {
+        let value = match serde_json::from_str::<Value>(line) {
+            Ok(value) => value,
+            Err(e) => return vec![Err(RpcError::new(None, PARSE_ERROR, e.to_string()))],
+        };
+
+        let requests = match value {
+            Value::Array(requests) if requests.is_empty() => {
+                return vec![Err(RpcError::new(None, INVALID_REQUEST, "Empty batch"))];
+            }
+
+            Value::Array(requests) => requests,
+            request => return Self::parse_request(request).into_iter().collect(),
+        };
+
+        let mut batch = Batch {
+            waiting_for: Vec::new(),
+            responses: Vec::new(),
+        };
+        let mut calls = Vec::new();
+
+        for request in requests.into_iter().filter_map(Self::parse_request) {
+            match request {
+                // errors without an id can't be told apart later, so they're added to the batch straight away
+                Err(e) if e.id.is_none() => batch.responses.push(e.to_json()),
+
+                result => {
+                    let id = match &result {
+                        Ok(call) => call.ids().id.clone(),
+                        Err(e) => e.id.clone(),
+                    };
+                    batch.waiting_for.extend(id);
+                    calls.push(result);
+                }
+            }
+        }
+
+        self.batches.push(batch);
+        calls
+    }
+
+    // None for a notification that couldn't be handled, since those never get a response (not even an error)
+    fn parse_request(request: Value) -> Option<Result<Call, RpcError>> {
+        let id = MessageIds::from_json(&request).id;
+        let error = |code, message: String| RpcError::new(id.clone(), code, message);
+
+        if request.get("jsonrpc") != Some(&json!("2.0")) {
+            return Some(Err(error(
+                INVALID_REQUEST,
+                "jsonrpc has to be \"2.0\"".into(),
+            )));
+        }
+
+        let method = match request.get("method") {
+            Some(Value::String(method)) => method.as_str(),
+            _ => {
+                return Some(Err(error(
+                    INVALID_REQUEST,
+                    "method has to be a string".into(),
+                )))
+            }
+        };
+
+        match Self::parse_call(method, request.get("params"), id.clone()) {
+            Err(e) if id.is_none() => {
+                log::warn!("Invalid notification {}: {}", request, e.message);
+                None
+            }
+
+            result => Some(result),
+        }
+    }
+
+    fn parse_call(
+        method: &str,
+        params: Option<&Value>,
+        id: Option<MessageId>,
+    ) -> Result<Call, RpcError> {
+        let error = |code, message: String| RpcError::new(id.clone(), code, message);
+
+        let params = match params {
+            None => json!({}),
+            Some(params @ Value::Object(_)) => params.clone(),
+            Some(_) => return Err(error(INVALID_PARAMS, "params has to be an object".into())),
+        };
+
+        let ids = MessageIds {
+            id: id.clone(),
+            ..Default::default()
+        };
+
+        let mut message = match method {
+            "show" | "prompt" | "select" | "describe" => {
+                serde_json::from_value::<Message>(params.clone())
+                    .map_err(|e| error(INVALID_PARAMS, e.to_string()))?
+            }
+
+            "quit" => return Ok(Call::Quit(ids)),
+            _ => {
+                return Err(error(
+                    METHOD_NOT_FOUND,
+                    format!("Unknown method {}", method),
+                ))
+            }
+        };
+
+        // other kinds refer to commands by their ids, which are the requests' ids here
+        if message.kind != MessageKind::Command {
+            let e = format!("{:?} messages can't be sent over JSON-RPC", message.kind);
+            return Err(error(INVALID_PARAMS, e));
+        }
+
+        // for the same reason, the Completions a plugin answers complete events with can't be sent
+        if message.completion == Some(Completion::Plugin) {
+            let e = "completion can't be Plugin over JSON-RPC, since Completions can't be sent";
+            return Err(error(INVALID_PARAMS, e.into()));
+        }
+
+        message.ids = ids;
+        message.deprecations = schema::deprecations(&params);
+        if method == "describe" {
+            message.kind = MessageKind::Describe;
+        }
+
+        match method {
+            "prompt" if message.canvas_mode.is_none() => {
+                message.canvas_mode = Some(CanvasMode::InputText);
+            }
+
+            "select" if message.canvas_mode.is_none() => match message.options.take() {
+                Some(options) => message.canvas_mode = Some(CanvasMode::Select(options.into())),
+                None => return Err(error(INVALID_PARAMS, "select needs options".into())),
+            },
+
+            _ => {}
+        }
+
+        Ok(Call::Message(Box::new(message)))
+    }
+
+    // The JSON-RPC version of a response, None if there shouldn't be one.
+    // Events aren't responses, so they're sent as notifications
+    pub fn response(response: &Response) -> Option<Value> {
+        let id = response.ids.id.as_ref();
+
+        if let Some(event) = &response.event {
+            return Some(json!({
+                "jsonrpc": "2.0",
+                "method": "event",
+                "params": { "id": id, "event": event, "data": response.data },
+            }));
+        }
+
+        // a notification
+        let id = id?;
+
+        Some(if let Some(error) = &response.error {
+            RpcError::new(Some(id.clone()), COMMAND_ERROR, error.clone()).to_json()
+        } else if response.timed_out {
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": TIMED_OUT, "message": "Timed out", "data": response.data },
+            })
+        } else {
+            json!({ "jsonrpc": "2.0", "id": id, "result": response.data })
+        })
+    }
+
+    // Returns the lines to write for a response (or error): nothing if it belongs to a batch that's
+    // still waiting for other responses, and the whole batch once it isn't
+    pub fn respond(&mut self, response: Value) -> Vec<String> {
+        let id = response
+            .get("id")
+            .and_then(|id| serde_json::from_value::<MessageId>(id.clone()).ok());
+
+        let batch = id.and_then(|id| {
+            self.batches.iter_mut().find_map(|batch| {
+                let i = batch.waiting_for.iter().position(|w| *w == id)?;
+                Some((batch, i))
+            })
+        });
+
+        match batch {
+            Some((batch, i)) => {
+                batch.waiting_for.remove(i);
+                batch.responses.push(response);
+                self.finished_batches()
+            }
+
+            None => vec![response.to_string()],
+        }
+    }
+
+    // The batches that aren't waiting for anything anymore, as lines to write
+    // (a batch of only notifications has nothing to write)
+    pub fn finished_batches(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        self.batches.retain_mut(|batch| {
+            if !batch.waiting_for.is_empty() {
+                return true;
+            }
+
+            if !batch.responses.is_empty() {
+                lines.push(Value::Array(std::mem::take(&mut batch.responses)).to_string());
+            }
+            false
+        });
+
+        lines
+    }
+}
+
+impl Call {
+    pub fn ids(&self) -> &MessageIds {
+        match self {
+            Call::Message(message) => &message.ids,
+            Call::Quit(ids) => ids,
+        }
+    }
+}
@@ -1,7 +1,27 @@
 pub mod app;
+pub mod assets;
 pub mod canvas;
+pub mod clock;
+pub mod completion;
+pub mod config;
+pub mod context;
+pub mod countdown;
+pub mod debug_overlay;
+pub mod error;
+pub mod font;
+pub mod frecency;
+#[cfg(feature = "ggez")]
+pub mod frontend;
+pub mod graphics;
+pub mod headless;
+pub mod history;
+pub mod input;
+pub mod jsonrpc;
 pub mod message;
+pub mod notifications;
+pub mod schema;
+pub mod screen;
+pub mod store;
 pub mod textbox;
-
-pub const SCREEN_WIDTH: f32 = 1920.0;
-pub const SCREEN_HEIGHT: f32 = 1080.0;
+pub mod theme;
+pub mod window;
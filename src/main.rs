@@ -1,107 +1,378 @@
-use ggez::conf::{WindowMode, WindowSetup};
+use clap::Parser;
+use ggez::conf::WindowSetup;
 use ggez::event::{self};
 use ggez::ContextBuilder;
-use joshu_core::app::App;
-use joshu_core::message::Message;
-use joshu_core::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use joshu_core::assets::Assets;
+use joshu_core::config::Config;
+use joshu_core::error::Error;
+use joshu_core::frecency::Frecency;
+use joshu_core::frontend::Frontend;
+use joshu_core::history::History;
+use joshu_core::message::Protocol;
+use joshu_core::schema;
+use joshu_core::screen::Screen;
+use joshu_core::theme::Theme;
+use joshu_core::window::{Corner, WindowPlacement};
 use std::fs::{File, OpenOptions};
-use std::io::Read;
-use std::{env, path};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
 use std::{
     io,
     sync::mpsc::{channel, Receiver},
     thread,
 };
 
+// Every flag can also be set in the config file (see Config), flags win over it
+#[derive(Parser)]
+#[command(name = "joshu-core", version)]
+struct Args {
+    /// Pipe to read messages from (stdin if not given)
+    #[arg(long = "in", value_name = "PATH")]
+    in_pipe: Option<PathBuf>,
+
+    /// Pipe to write responses to (stdout if not given)
+    #[arg(long = "out", value_name = "PATH")]
+    out_pipe: Option<PathBuf>,
+
+    /// Directory with textbox.png and the avatar images
+    #[arg(long, value_name = "DIR")]
+    res_dir: Option<PathBuf>,
+
+    /// Config file to use instead of ~/.config/joshu/config.toml
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Name of a built-in theme (light or dark) or path to a theme file
+    #[arg(long)]
+    theme: Option<String>,
+
+    /// One of off, error, warn, info, debug and trace
+    #[arg(long, value_name = "LEVEL")]
+    log_level: Option<String>,
+
+    /// File to write logs to instead of stderr
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Start with the debug overlay shown (F12 toggles it)
+    #[arg(long)]
+    debug_overlay: bool,
+
+    /// One of joshu and jsonrpc
+    #[arg(long, value_parser = parse_name::<Protocol>)]
+    protocol: Option<Protocol>,
+
+    /// Print the JSON Schema of messages and responses, then exit
+    #[arg(long)]
+    print_schema: bool,
+
+    /// Open as a normal window (same as --window windowed)
+    #[arg(long, conflicts_with = "window")]
+    windowed: bool,
+
+    /// One of overlay, windowed and corner
+    #[arg(long, value_name = "PLACEMENT", value_parser = parse_name::<WindowPlacement>)]
+    window: Option<WindowPlacement>,
+
+    /// Index of the monitor to open on
+    #[arg(long)]
+    monitor: Option<usize>,
+
+    /// Position of the window when windowed, like 100,50
+    #[arg(long, value_name = "X,Y", value_parser = parse_position)]
+    position: Option<[i32; 2]>,
+
+    /// Size of the window when windowed, or of the corner, like 800x600
+    #[arg(long, value_name = "WxH", value_parser = parse_size)]
+    size: Option<[u32; 2]>,
+
+    /// One of top_left, top_right, bottom_left and bottom_right
+    #[arg(long, value_parser = parse_name::<Corner>)]
+    corner: Option<Corner>,
+
+    /// Keep the window above all others
+    #[arg(long)]
+    always_on_top: bool,
+
+    /// Pretend the screen has this size (and DPI scale), for testing
+    #[arg(long, value_name = "WxH[@SCALE]", value_parser = parse_screen)]
+    screen: Option<Screen>,
+
+    // the old way of giving the pipes: joshu-core OUT [IN]
+    #[arg(hide = true, num_args = 0..=2)]
+    pipes: Vec<PathBuf>,
+}
+
+impl Args {
+    // Applies the flags that were given on top of the config
+    fn apply(self, config: &mut Config) {
+        let mut pipes = self.pipes.into_iter();
+        if let Some(out_pipe) = self.out_pipe.or(pipes.next()) {
+            config.out_pipe = Some(out_pipe);
+        }
+        if let Some(in_pipe) = self.in_pipe.or(pipes.next()) {
+            config.in_pipe = Some(in_pipe);
+        }
+
+        if self.res_dir.is_some() {
+            config.res_dir = self.res_dir;
+        }
+        if self.log_level.is_some() {
+            config.log_level = self.log_level;
+        }
+        if self.log_file.is_some() {
+            config.log_file = self.log_file;
+        }
+        config.debug_overlay |= self.debug_overlay;
+        if let Some(protocol) = self.protocol {
+            config.protocol = protocol;
+        }
+
+        let window = &mut config.window;
+        if self.windowed {
+            window.placement = WindowPlacement::Windowed;
+        }
+        if let Some(placement) = self.window {
+            window.placement = placement;
+        }
+        if self.monitor.is_some() {
+            window.monitor = self.monitor;
+        }
+        if self.position.is_some() {
+            window.position = self.position;
+        }
+        if self.size.is_some() {
+            window.size = self.size;
+        }
+        if let Some(corner) = self.corner {
+            window.corner = corner;
+        }
+        window.always_on_top |= self.always_on_top;
+    }
+}
+
 fn main() {
-    let args = env::args();
-
-    let mut out_pipe: Option<File> = None;
-    let mut in_pipe: Option<File> = None;
-    if args.len() == 2 {
-        let out_path = &args.collect::<Vec<String>>()[1];
-        let out_fd = OpenOptions::new()
-            .write(true)
-            .open(out_path)
-            .expect(&format!("Pipe {} does not exist", out_path));
-
-        out_pipe = Some(out_fd);
-    } else if args.len() == 3 {
-        let args_vec = args.collect::<Vec<String>>();
-        let out_path = &args_vec[1];
-        let in_path = &args_vec[2];
-
-        let out_fd = OpenOptions::new()
-            .write(true)
-            .open(out_path)
-            .expect(&format!("Pipe {} does not exist", out_path));
-
-        let in_fd = OpenOptions::new()
-            .read(true)
-            .open(in_path)
-            .expect(&format!("Pipe {} does not exist", in_path));
-
-        out_pipe = Some(out_fd);
-        in_pipe = Some(in_fd);
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Error> {
+    let args = Args::parse();
+
+    if args.print_schema {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&schema::json_schema()).unwrap()
+        );
+        return Ok(());
     }
 
-    let resource_dir = path::PathBuf::from("./res");
+    let mut config = Config::load_or_default(args.config.as_deref()).map_err(Error::Config)?;
+
+    // JOSHU_THEME goes in between --theme and the config file, see load_theme
+    let theme_override = args.theme.clone();
+    let screen_override = args.screen;
+    args.apply(&mut config);
+
+    init_logger(
+        config.log_level.as_deref().unwrap_or("warn"),
+        config.log_file.as_deref(),
+    );
+
+    if let Some(screen) = screen_override {
+        config.window.placement = WindowPlacement::Windowed;
+        config.window.size = Some([screen.width as u32, screen.height as u32]);
+    }
+
+    let open_pipe = |path: &PathBuf, options: &mut OpenOptions| {
+        options.open(path).map_err(|source| Error::Pipe {
+            path: path.clone(),
+            source,
+        })
+    };
+
+    let out_pipe = config
+        .out_pipe
+        .as_ref()
+        .map(|path| open_pipe(path, OpenOptions::new().write(true)))
+        .transpose()?;
+
+    let in_pipe = config
+        .in_pipe
+        .as_ref()
+        .map(|path| open_pipe(path, OpenOptions::new().read(true)))
+        .transpose()?;
+
+    let assets = match config.res_dir.clone().or_else(Config::find_res_dir) {
+        Some(resource_dir) => {
+            log::info!("Using resources from {}", resource_dir.display());
+            Assets::load(&resource_dir)
+        }
+
+        None => {
+            log::warn!("Could not find the resource directory, use --res-dir to give it");
+            Assets::blank()
+        }
+    };
+
+    let theme = load_theme(theme_override.as_deref(), config.theme.as_deref())?;
 
     let (mut ctx, event_loop) = ContextBuilder::new("Joshu", "")
         .window_setup(WindowSetup {
             title: String::from("Project Joshu"),
             ..Default::default()
         })
-        .window_mode(WindowMode {
-            width: SCREEN_WIDTH,
-            height: SCREEN_HEIGHT,
-            transparent: true,
-            fullscreen_type: ggez::conf::FullscreenType::True,
-            ..Default::default()
-        })
-        .add_resource_path(resource_dir)
+        .window_mode(config.window.window_mode())
         .build()
-        .expect("Could not create ggez context!");
+        .map_err(|e| Error::Window(e.to_string()))?;
+
+    let screen = config.window.place(&ctx);
+    let screen = screen_override.unwrap_or(screen);
 
     let receiver = run_input_receiver(in_pipe);
 
-    let my_game = App::new(&mut ctx, receiver, out_pipe);
+    let output: Box<dyn Write> = match out_pipe {
+        Some(out_pipe) => Box::new(out_pipe),
+        None => Box::new(io::stdout()),
+    };
+
+    let mut frontend = Frontend::new(&mut ctx, receiver, output, assets, theme, screen);
+    frontend.app_mut().set_debug_overlay(config.debug_overlay);
+    frontend.app_mut().set_protocol(config.protocol);
+    if let Some(path) = config.history_file.or_else(History::default_path) {
+        frontend.app_mut().set_history(History::load(&path));
+    }
+    if let Some(path) = config.frecency_file.or_else(Frecency::default_path) {
+        frontend.app_mut().set_frecency(Frecency::load(&path));
+    }
 
-    event::run(ctx, event_loop, my_game);
+    event::run(ctx, event_loop, frontend);
 }
 
-fn run_input_receiver(mut in_pipe: Option<File>) -> Receiver<Message> {
-    let (sender, receiver) = channel();
+// Logs go to the log file if there is one, or to stderr, since stdout is where responses go
+fn init_logger(level: &str, file: Option<&Path>) {
+    let level = level.parse().unwrap_or_else(|_| {
+        eprintln!("Invalid log level {}, using warn", level);
+        log::LevelFilter::Warn
+    });
+
+    let target = match file {
+        Some(path) => match File::create(path) {
+            Ok(file) => env_logger::Target::Pipe(Box::new(file)),
+            Err(e) => {
+                eprintln!(
+                    "Could not open log file {}, logging to stderr: {}",
+                    path.display(),
+                    e
+                );
+                env_logger::Target::Stderr
+            }
+        },
+        None => env_logger::Target::Stderr,
+    };
 
-    // this buffer is used to read data from the pipe
-    // for some fucking reason, reading to a string doesn't work, so I'm just using a huge 0.5MB static buffer instead
-    // TODO: either figure out why read_to_string doesn't work or add multiple smaller buffers together to only allocate the amount necessary
-    let mut buffer = [0; 1000 * 500];
+    env_logger::Builder::new()
+        .filter_level(level)
+        .target(target)
+        .init();
+}
 
-    thread::spawn(move || loop {
-        let (message, size) = match &mut in_pipe {
-            Some(pipe) => {
-                let size = pipe.read(&mut buffer).unwrap();
+// Parses values like "bottom_left" into the enum they're the serialized name of
+fn parse_name<T: serde::de::DeserializeOwned>(name: &str) -> Result<T, String> {
+    serde_json::from_value(name.replace('-', "_").into())
+        .map_err(|_| format!("invalid value {}", name))
+}
 
-                let buffer_str = std::str::from_utf8(&buffer[..size]).unwrap();
+fn parse_position(position: &str) -> Result<[i32; 2], String> {
+    let error = || format!("invalid position {}, it needs to be like 100,50", position);
 
-                (buffer_str.to_string(), size)
-            }
+    let (x, y) = position.split_once(',').ok_or_else(error)?;
+    Ok([
+        x.trim().parse().map_err(|_| error())?,
+        y.trim().parse().map_err(|_| error())?,
+    ])
+}
 
-            None => {
-                let mut buffer = String::new();
-                let size = io::stdin().read_line(&mut buffer).unwrap();
+fn parse_size(size: &str) -> Result<[u32; 2], String> {
+    let error = || format!("invalid size {}, it needs to be like 800x600", size);
 
-                (buffer, size)
-            }
+    let (width, height) = size.split_once('x').ok_or_else(error)?;
+    Ok([
+        width.trim().parse().map_err(|_| error())?,
+        height.trim().parse().map_err(|_| error())?,
+    ])
+}
+
+fn parse_screen(screen: &str) -> Result<Screen, String> {
+    Screen::parse(screen).ok_or(format!(
+        "invalid screen {}, it needs to be like 1366x768",
+        screen
+    ))
+}
+
+// The theme can be the name of a built-in one (light or dark) or the path to a theme file.
+// --theme is used over JOSHU_THEME, which is used over the config file
+fn load_theme(from_args: Option<&str>, from_config: Option<&str>) -> Result<Theme, Error> {
+    let theme = match from_args
+        .map(String::from)
+        .or(std::env::var("JOSHU_THEME").ok())
+        .or(from_config.map(String::from))
+    {
+        Some(theme) => theme,
+        None => return Ok(Theme::light()),
+    };
+
+    if let Some(theme) = Theme::builtin(&theme) {
+        return Ok(theme);
+    }
+
+    Theme::load(Path::new(&theme)).map_err(Error::Theme)
+}
+
+// Reads messages on another thread until the input ends (or can't be read anymore),
+// then drops the sender so the app knows no more are coming
+fn run_input_receiver(in_pipe: Option<File>) -> Receiver<String> {
+    let (sender, receiver) = channel();
+
+    thread::spawn(move || {
+        // messages are one per line, however the reads happen to split them up
+        let mut reader: Box<dyn BufRead> = match in_pipe {
+            Some(pipe) => Box::new(BufReader::new(pipe)),
+            None => Box::new(io::stdin().lock()),
         };
+        let mut line = Vec::new();
+
+        loop {
+            line.clear();
+            let message = match reader.read_until(b'\n', &mut line) {
+                Ok(0) => {
+                    log::info!("Reached the end of the input");
+                    return;
+                }
+
+                // a message that isn't utf-8 won't parse, so it's dropped by the app like any other invalid one
+                Ok(_) => String::from_utf8_lossy(&line).into_owned(),
+
+                Err(e) => {
+                    log::error!("Could not read the input: {}", e);
+                    return;
+                }
+            };
+
+            // blank lines are skipped
+            if message.trim().is_empty() {
+                continue;
+            }
 
-        if size > 0 {
-            if let Ok(message) = serde_json::from_str::<Message>(&message) {
-                sender.send(message).unwrap();
+            // it's parsed by the app, so invalid messages can be logged and shown in the debug overlay
+            if sender.send(message).is_err() {
+                // the app is gone
+                return;
             }
         }
     });
 
-    return receiver;
+    receiver
 }
@@ -1,10 +1,181 @@
-use crate::canvas::CanvasMode;
+use std::fmt;
+
+use crate::{canvas::CanvasMode, completion::Completion};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+// Ids can be any json scalar, and are sent back exactly as they came in (1 and "1" are different ids)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum MessageId {
+    String(String),
+    Number(serde_json::Number),
+    Bool(bool),
+}
+
+impl fmt::Display for MessageId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MessageId::String(s) => write!(f, "{}", s),
+            MessageId::Number(n) => write!(f, "{}", n),
+            MessageId::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+impl From<&str> for MessageId {
+    fn from(id: &str) -> Self {
+        MessageId::String(id.to_string())
+    }
+}
+
+// Everything a response is matched to its message with, which is copied from the message to every response for it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct MessageIds {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<MessageId>,
+    // the id of the response (or message) this message follows up on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<MessageId>,
+    // for plugins to tell apart the messages of different flows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<MessageId>,
+}
+
+impl MessageIds {
+    // The ids of a message that couldn't be parsed, leaving out the ones that aren't valid either
+    pub fn from_json(message: &serde_json::Value) -> Self {
+        let get = |key| {
+            message
+                .get(key)
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+        };
+
+        Self {
+            id: get("id"),
+            reply_to: get("reply_to"),
+            session: get("session"),
+        }
+    }
+}
+
+// How messages and responses are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Protocol {
+    // the messages and responses below, one per line
+    #[default]
+    Joshu,
+    // every message is a JSON-RPC 2.0 request, and responses follow JSON-RPC 2.0 too (see jsonrpc)
+    #[serde(rename = "jsonrpc")]
+    JsonRpc,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub enum MessageKind {
+    // a new command, which gets queued if another one is executing
+    #[default]
+    Command,
+    // changes the currently executing command (the one with the same id) without finishing it
+    Update,
+    // finishes the currently executing command (the one with the same id)
+    Finish,
+    // same as Finish, but the command's response says it was cancelled
+    Cancel,
+    // replaces the options of the Select shown by the currently executing command (the one with the same id)
+    SetOptions,
+    // responds with the ids of the executing, suspended and queued commands
+    InspectQueue,
+    // removes the queued commands with the same id
+    DropQueued,
+    // removes every queued command
+    ClearQueue,
+    // shows textbox_text (and avatar_emotion) in a corner for a while, without waiting for
+    // or blocking the executing command
+    Notification,
+    // responds with the protocol version and what joshu-core supports (see schema::description)
+    Describe,
+    // forgets which options were picked in Selects with the message's remember_key (or with any key)
+    ResetRemembered,
+    // the options the text of the InputText shown by the currently executing command (the one with
+    // the same id) can be completed to, sent after a complete event
+    Completions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct Message {
-    pub id: Option<String>,
+    #[serde(default)]
+    pub kind: MessageKind,
+    #[serde(flatten)]
+    pub ids: MessageIds,
     pub avatar_emotion: Option<String>,
     pub textbox_text: Option<String>,
     pub canvas_mode: Option<CanvasMode>,
+    // queued commands with a higher priority are executed first
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub priority: i32,
+    // executes the command straight away, the one that was executing continues once it's done
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interrupt: bool,
+    // finishes the command if it's still executing after this long, with default as its data
+    // (null if not given) and timed_out set in the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<serde_json::Value>,
+    // draws a bar under the canvas showing how much of timeout_ms is left
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub countdown: bool,
+    // only used by Notification messages: how long it's shown, and whether a response is sent once it's gone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub respond: bool,
+    // only used by SetOptions and Completions messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<String>>,
+    // only used with an InputText canvas_mode: what's submitted is remembered under this key,
+    // and Up, Down and Ctrl+R go through what was submitted before
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_key: Option<String>,
+    // only used with a Select canvas_mode (and by ResetRemembered messages): the picked options are
+    // remembered under this key, and the ones picked often and lately are shown first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remember_key: Option<String>,
+    // only used with an InputText canvas_mode: what Tab completes the text with (paths if not given)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion: Option<Completion>,
+    // theme keys to change for this command only (same format as a theme file)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<serde_json::Value>,
+    // the version of the protocol the plugin was written for, see App::negotiate_protocol_version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_version: Option<u32>,
+    // the old forms the message used, found when it's read (see schema::deprecations)
+    #[serde(skip)]
+    pub deprecations: Vec<String>,
+}
+
+fn is_zero(n: &i32) -> bool {
+    *n == 0
+}
+
+// What gets sent back (as a single line of json) when a command has finished
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct Response {
+    #[serde(flatten)]
+    pub ids: MessageIds,
+    pub data: serde_json::Value,
+    // set when the message couldn't be handled (e.g. an Update for a command that isn't executing)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // set when this isn't the command's final response, but an event that happened while it's executing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    // set when the command was finished because its timeout_ms passed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+    // the old forms the message used, only sent once a protocol_version was negotiated
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
@@ -0,0 +1,204 @@
+use std::{rc::Rc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::Clock,
+    context::Context,
+    font::FontChain,
+    graphics::{Color, DrawList, Image, Rect, Text, TextFragment, Vec2},
+    message::MessageIds,
+    window::Corner,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    pub corner: Corner,
+    pub width: f32,
+    pub margin: f32,  // between the notifications and the edges of the screen
+    pub spacing: f32, // between notifications
+    pub padding: f32,
+    pub font_size: f32,
+    pub avatar_size: f32, // height of the avatar, if the notification has an emotion
+    pub duration_ms: u64, // how long a notification is shown, if the message doesn't say
+    pub max_shown: usize, // older notifications are dismissed early when there's more than this
+    #[serde(with = "crate::theme::color")]
+    pub text_color: Color,
+    pub background_outline_width: f32,
+    #[serde(with = "crate::theme::color")]
+    pub background_outline_color: Color,
+    #[serde(with = "crate::theme::color")]
+    pub background_color: Color,
+    #[serde(skip)]
+    pub font: FontChain,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            corner: Corner::TopRight,
+            width: 450.0,
+            margin: 20.0,
+            spacing: 10.0,
+            padding: 15.0,
+            font_size: 24.0,
+            avatar_size: 80.0,
+            duration_ms: 4000,
+            max_shown: 5,
+            text_color: Color::BLACK,
+            background_outline_width: 2.0,
+            background_outline_color: Color::BLACK,
+            background_color: Color::WHITE,
+            font: Default::default(),
+        }
+    }
+}
+
+struct Notification {
+    ids: MessageIds,
+    text: Text,
+    avatar: Option<Image>,
+    shown_at: Duration,
+    duration: Duration,
+    // whether a response is sent once it's dismissed
+    respond: bool,
+}
+
+// Notifications are shown stacked in a corner on top of everything else, and go away on their own.
+// They don't wait for the executing command, or make anything else wait for them
+pub struct Notifications {
+    config: NotificationConfig,
+    notifications: Vec<Notification>,
+    clock: Rc<dyn Clock>,
+}
+
+impl Notifications {
+    pub fn new(ctx: &Context, config: &NotificationConfig) -> Self {
+        Self {
+            config: config.clone(),
+            notifications: Vec::new(),
+            clock: ctx.clock(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notifications.is_empty()
+    }
+
+    pub fn show(
+        &mut self,
+        ids: MessageIds,
+        text: &str,
+        avatar: Option<Image>,
+        duration: Option<Duration>,
+        respond: bool,
+    ) {
+        let mut text = self.config.font.text(TextFragment {
+            text: text.to_string(),
+            color: Some(self.config.text_color),
+            scale: Some(self.config.font_size),
+            ..Default::default()
+        });
+        text.set_bounds(Vec2::new(self.text_width(avatar.as_ref()), f32::MAX));
+
+        self.notifications.push(Notification {
+            ids,
+            text,
+            avatar,
+            shown_at: self.clock.time_since_start(),
+            duration: duration.unwrap_or(Duration::from_millis(self.config.duration_ms)),
+            respond,
+        });
+    }
+
+    // Removes the notifications that were shown for long enough (or that there's no room for anymore),
+    // returning the ids of the ones that should get a response
+    pub fn update(&mut self) -> Vec<MessageIds> {
+        let now = self.clock.time_since_start();
+        let too_many = self
+            .notifications
+            .len()
+            .saturating_sub(self.config.max_shown);
+
+        let mut dismissed = Vec::new();
+        let mut i = 0;
+        self.notifications.retain(|notification| {
+            let keep = i >= too_many && now - notification.shown_at < notification.duration;
+            i += 1;
+
+            if !keep && notification.respond {
+                dismissed.push(notification.ids.clone());
+            }
+            keep
+        });
+
+        dismissed
+    }
+
+    fn avatar_scale(&self, avatar: &Image) -> f32 {
+        self.config.avatar_size / avatar.height() as f32
+    }
+
+    fn text_width(&self, avatar: Option<&Image>) -> f32 {
+        let avatar_width = match avatar {
+            Some(avatar) => avatar.width() as f32 * self.avatar_scale(avatar) + self.config.padding,
+            None => 0.0,
+        };
+
+        self.config.width - 2.0 * self.config.padding - avatar_width
+    }
+
+    pub fn draw(&self, ctx: &Context, draw_list: &mut DrawList) {
+        let screen = ctx.screen();
+        let config = &self.config;
+
+        let x = match config.corner {
+            Corner::TopLeft | Corner::BottomLeft => config.margin,
+            Corner::TopRight | Corner::BottomRight => screen.width - config.margin - config.width,
+        };
+        let from_top = matches!(config.corner, Corner::TopLeft | Corner::TopRight);
+
+        // the oldest notification is the one closest to the corner
+        let mut offset = config.margin;
+
+        for notification in &self.notifications {
+            let text_height = ctx.measure(&notification.text).h;
+            let content_height = match &notification.avatar {
+                Some(_) => text_height.max(config.avatar_size),
+                None => text_height,
+            };
+            let height = content_height + 2.0 * config.padding;
+
+            let y = if from_top {
+                offset
+            } else {
+                screen.height - offset - height
+            };
+            offset += height + config.spacing;
+
+            let background_rect = Rect::new(x, y, config.width, height);
+            let mut outline_rect = background_rect;
+            outline_rect.x -= config.background_outline_width;
+            outline_rect.y -= config.background_outline_width;
+            outline_rect.w += 2.0 * config.background_outline_width;
+            outline_rect.h += 2.0 * config.background_outline_width;
+
+            draw_list.rect(&outline_rect, &config.background_outline_color);
+            draw_list.rect(&background_rect, &config.background_color);
+
+            let mut text_x = x + config.padding;
+            if let Some(avatar) = &notification.avatar {
+                let scale = self.avatar_scale(avatar);
+                draw_list.image(
+                    avatar,
+                    Vec2::new(text_x, y + config.padding),
+                    Vec2::new(scale, scale),
+                );
+                text_x += avatar.width() as f32 * scale + config.padding;
+            }
+
+            draw_list.text(&notification.text, Vec2::new(text_x, y + config.padding));
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use schemars::gen::SchemaSettings;
+use serde_json::{json, Value};
+
+use crate::{
+    assets::AVATAR_EMOTIONS,
+    message::{Message, Response},
+};
+
+// The version of the message and response format. It only goes up when something plugins rely on
+// changes, and the old forms keep working (with a warning) for at least one version
+pub const PROTOCOL_VERSION: u32 = 1;
+// the oldest version that's still accepted
+pub const OLDEST_PROTOCOL_VERSION: u32 = 1;
+
+pub const CANVAS_MODES: [&str; 6] = ["InputText", "Select", "Image", "Layout", "Form", "Progress"];
+
+// What a Describe message responds with
+pub fn description(protocol_version: u32) -> Value {
+    json!({
+        "protocol_version": protocol_version,
+        "supported_protocol_versions": (OLDEST_PROTOCOL_VERSION..=PROTOCOL_VERSION).collect::<Vec<_>>(),
+        "canvas_modes": CANVAS_MODES,
+        "avatar_emotions": AVATAR_EMOTIONS,
+    })
+}
+
+// A JSON Schema (draft 7) with a Message and a Response definition, printed by --print-schema
+pub fn json_schema() -> Value {
+    let mut gen = SchemaSettings::draft07().into_generator();
+    gen.subschema_for::<Message>();
+    gen.subschema_for::<Response>();
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": format!("joshu-core protocol version {}", PROTOCOL_VERSION),
+        "definitions": gen.definitions(),
+    })
+}
+
+// The old forms a message uses, each with what to use instead. They still work, but
+// will stop working once the protocol version goes past them
+pub fn deprecations(message: &Value) -> Vec<String> {
+    let mut deprecations = Vec::new();
+
+    if let Some(canvas_mode) = message.get("canvas_mode") {
+        canvas_mode_deprecations(canvas_mode, &mut deprecations);
+    }
+
+    deprecations
+}
+
+fn canvas_mode_deprecations(canvas_mode: &Value, deprecations: &mut Vec<String>) {
+    // from before messages had their own timeout_ms
+    let image_timeout = canvas_mode
+        .get("Image")
+        .is_some_and(|image| image.get("timeout_ms").is_some());
+    if image_timeout {
+        let deprecation =
+            String::from("Image's timeout_ms is deprecated, give the message a timeout_ms instead");
+        if !deprecations.contains(&deprecation) {
+            deprecations.push(deprecation);
+        }
+    }
+
+    // the widgets in a layout can use old forms too
+    let children = canvas_mode
+        .get("Layout")
+        .and_then(|layout| layout.get("children"))
+        .and_then(Value::as_array);
+    for child in children.into_iter().flatten() {
+        if let Some(mode) = child.get("mode") {
+            canvas_mode_deprecations(mode, deprecations);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+// Everything (the default theme, the avatar and textbox images) is made for a 1080p screen,
+// on other screens it's all scaled by Screen::scale
+pub const REFERENCE_HEIGHT: f32 = 1080.0;
+
+// The size of the screen joshu is drawn on, in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Screen {
+    pub width: f32,
+    pub height: f32,
+    // how much the 1080p layout is scaled by
+    pub scale: f32,
+    // what the monitor reports, which only makes things sharper and never changes how big they are
+    pub dpi_scale: f32,
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Self {
+            width: 1920.0,
+            height: REFERENCE_HEIGHT,
+            scale: 1.0,
+            dpi_scale: 1.0,
+        }
+    }
+}
+
+impl Screen {
+    // The 1080p layout is scaled to the screen's height, so it takes up the same part of any screen
+    // (whatever its DPI scale, since the drawing is in physical pixels anyway)
+    pub fn new(width: f32, height: f32, dpi_scale: f32) -> Self {
+        Self {
+            width,
+            height,
+            scale: height / REFERENCE_HEIGHT,
+            dpi_scale,
+        }
+    }
+
+    // Parses screens like "1366x768", or "3840x2160@2" to also give the DPI scale (1 if not given)
+    pub fn parse(screen: &str) -> Option<Self> {
+        let (size, dpi_scale) = match screen.split_once('@') {
+            Some((size, dpi_scale)) => (size, dpi_scale.trim().parse().ok()?),
+            None => (screen, 1.0),
+        };
+
+        let (width, height) = size.split_once('x')?;
+        let width: u32 = width.trim().parse().ok()?;
+        let height: u32 = height.trim().parse().ok()?;
+
+        if width == 0 || height == 0 || dpi_scale <= 0.0 {
+            return None;
+        }
+
+        Some(Self::new(width as f32, height as f32, dpi_scale))
+    }
+
+    pub fn center_x(&self) -> f32 {
+        self.width / 2.0
+    }
+
+    pub fn center_y(&self) -> f32 {
+        self.height / 2.0
+    }
+}
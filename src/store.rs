@@ -0,0 +1,33 @@
+use std::{fs, path::Path};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+// Reads something joshu remembers between runs (what is what it's called in the logs).
+// A missing file is the default, an invalid one is logged and replaced the next time it's saved
+pub fn load<T: DeserializeOwned + Default>(path: &Path, what: &str) -> T {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            log::warn!("Invalid {} {}: {}", what, path.display(), e);
+            T::default()
+        }),
+
+        Err(e) => {
+            if path.exists() {
+                log::warn!("Could not read {} {}: {}", what, path.display(), e);
+            }
+            T::default()
+        }
+    }
+}
+
+// Writes it as json, creating the directory if needed. Failing to is only logged
+pub fn save<T: Serialize>(path: &Path, value: &T, what: &str) {
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(path, serde_json::to_string(value).unwrap()));
+
+    if let Err(e) = result {
+        log::error!("Could not save {} {}: {}", what, path.display(), e);
+    }
+}
@@ -1,66 +1,107 @@
-use std::{str::from_utf8, sync::mpsc::Sender, time};
+use std::{rc::Rc, sync::mpsc::Sender, time};
 
-use ggez::{
-    glam::Vec2,
-    graphics::{Canvas, Color, DrawParam, Drawable, Image, PxScale, Text, TextFragment},
-    Context,
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::FinishedMessage,
+    clock::Clock,
+    context::Context,
+    font::FontChain,
+    graphics::{Color, DrawList, Image, Text, TextFragment, Vec2},
+    screen::Screen,
 };
 
-use crate::{app::FinishedMessage, SCREEN_HEIGHT, SCREEN_WIDTH};
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextboxConfig {
+    pub font_size: f32,
+    pub horizontal_padding: f32,
+    pub vertical_padding: f32,
+    pub text_animation_speed: u128, // amount of time in milliseconds in between characters appearing
+    pub disappear_speed: u128,      // amount of time in milliseconds before the textbox disappears
+    #[serde(with = "crate::theme::color")]
+    pub text_color: Color,
+    #[serde(skip)]
+    pub font: FontChain,
+}
 
-const FONT_SIZE: f32 = 32.0;
-const HORIZONTAL_PADDING: f32 = 10.0;
-const VERTICAL_PADDING: f32 = 15.0;
-const TEXT_ANIMATION_SPEED: u128 = 30; // amount of time in milliseconds in between characters appearing
-const DISAPPEAR_SPEED: u128 = 2000; // amount of time in milliseconds before the textbox disappears
+impl Default for TextboxConfig {
+    fn default() -> Self {
+        Self {
+            font_size: 32.0,
+            horizontal_padding: 10.0,
+            vertical_padding: 15.0,
+            text_animation_speed: 30,
+            disappear_speed: 2000,
+            text_color: Color::WHITE,
+            font: Default::default(),
+        }
+    }
+}
 
 pub struct Textbox {
+    config: TextboxConfig,
     shown: bool,
     entire_text: String,
     displayed_text: Text,
     time_last_char_appeared: time::Duration,
     time_finished: Option<time::Duration>,
     image: Image,
+    avatar_image_width: f32,
     bounds: Vec2,
     finished_sender: Sender<FinishedMessage>,
     finished_sender_enabled: bool,
+    // when chars appear and when the textbox disappears is timed with it
+    clock: Rc<dyn Clock>,
 }
 
 impl Textbox {
     // avatar_image_width is used to compute the displayed text bounds
     pub fn new(
         ctx: &Context,
+        config: &TextboxConfig,
+        image: Image,
         avatar_image_width: f32,
         finished_sender: Sender<FinishedMessage>,
     ) -> Self {
         let displayed_text = Text::new("");
-        let image = Image::from_path(ctx, "/textbox.png").expect("Could not load textbox image!");
-
-        let bounds = Vec2::new(
-            SCREEN_WIDTH - avatar_image_width - (2.0 * HORIZONTAL_PADDING),
-            f32::MAX,
-        );
 
         Self {
+            config: config.clone(),
             shown: false,
             displayed_text,
             entire_text: String::new(),
-            time_last_char_appeared: ctx.time.time_since_start(),
+            time_last_char_appeared: ctx.clock().time_since_start(),
             time_finished: None,
             image,
-            bounds,
+            avatar_image_width,
+            bounds: Self::text_bounds(ctx, config, avatar_image_width),
             finished_sender,
             finished_sender_enabled: false,
+            clock: ctx.clock(),
         }
     }
 
-    pub fn set_text(&mut self, ctx: &Context, text: &String, finished_listener_enabled: bool) {
+    fn text_bounds(ctx: &Context, config: &TextboxConfig, avatar_image_width: f32) -> Vec2 {
+        Vec2::new(
+            ctx.screen().width - avatar_image_width - (2.0 * config.horizontal_padding),
+            f32::MAX,
+        )
+    }
+
+    // Only affects text shown from now on
+    pub fn set_config(&mut self, ctx: &Context, config: &TextboxConfig) {
+        self.config = config.clone();
+        self.bounds = Self::text_bounds(ctx, config, self.avatar_image_width);
+    }
+
+    pub fn set_text(&mut self, text: &str, finished_listener_enabled: bool) {
         self.shown = true;
 
         self.time_finished = None;
-        self.time_last_char_appeared = ctx.time.time_since_start();
+        self.time_last_char_appeared = self.clock.time_since_start();
 
-        self.entire_text = text.clone();
+        self.entire_text = text.to_string();
 
         self.displayed_text = Text::new("");
         self.displayed_text.set_bounds(self.bounds);
@@ -78,9 +119,9 @@ impl Textbox {
                 // if text has finished displaying
                 Some(time_finished) => {
                     if self.finished_sender_enabled {
-                        let elapsed = ctx.time.time_since_start() - time_finished;
+                        let elapsed = self.clock.time_since_start() - time_finished;
 
-                        if elapsed.as_millis() >= DISAPPEAR_SPEED {
+                        if elapsed.as_millis() >= self.config.disappear_speed {
                             self.hide();
 
                             self.finished_sender.send(FinishedMessage::Textbox).unwrap();
@@ -90,10 +131,12 @@ impl Textbox {
 
                 // if text hasn't finished displaying
                 None => {
-                    let elapsed = ctx.time.time_since_start() - self.time_last_char_appeared;
+                    let elapsed = self.clock.time_since_start() - self.time_last_char_appeared;
 
-                    if !self.entire_text.is_empty() && elapsed.as_millis() >= TEXT_ANIMATION_SPEED {
-                        self.time_last_char_appeared = ctx.time.time_since_start();
+                    if !self.entire_text.is_empty()
+                        && elapsed.as_millis() >= self.config.text_animation_speed
+                    {
+                        self.time_last_char_appeared = self.clock.time_since_start();
 
                         let index: usize = self.displayed_text.contents().len();
 
@@ -101,7 +144,7 @@ impl Textbox {
                             self.add_char(ctx);
                         } else {
                             // text has finished displaying
-                            self.time_finished = Some(ctx.time.time_since_start());
+                            self.time_finished = Some(self.clock.time_since_start());
                         }
                     }
                 }
@@ -109,19 +152,32 @@ impl Textbox {
         }
     }
 
-    pub fn draw(&self, canvas: &mut Canvas) {
+    // The image is stretched to the width of the screen, and scaled like everything else vertically
+    fn image_scale(&self, screen: &Screen) -> Vec2 {
+        Vec2::new(screen.width / self.image.width() as f32, screen.scale)
+    }
+
+    fn image_height(&self, screen: &Screen) -> f32 {
+        self.image.height() as f32 * self.image_scale(screen).y
+    }
+
+    pub fn draw(&self, ctx: &Context, draw_list: &mut DrawList) {
         if self.shown {
-            canvas.draw(
+            let screen = ctx.screen();
+            let image_y = screen.height - self.image_height(&screen);
+
+            draw_list.image(
                 &self.image,
-                DrawParam::new().dest(Vec2::new(0.0, SCREEN_HEIGHT - self.image.height() as f32)),
+                Vec2::new(0.0, image_y),
+                self.image_scale(&screen),
             );
 
-            canvas.draw(
+            draw_list.text(
                 &self.displayed_text,
-                DrawParam::new().dest(Vec2::new(
-                    HORIZONTAL_PADDING,
-                    SCREEN_HEIGHT - self.image.height() as f32 + VERTICAL_PADDING,
-                )),
+                Vec2::new(
+                    self.config.horizontal_padding,
+                    image_y + self.config.vertical_padding,
+                ),
             );
         }
     }
@@ -129,44 +185,42 @@ impl Textbox {
     fn add_char(&mut self, ctx: &Context) {
         let index: usize = self.displayed_text.contents().len();
 
-        let char = self.entire_text.as_bytes()[index];
-        let mut bytes = vec![char];
+        let mut chars = self.entire_text[index..].chars();
+        let char = chars.next().unwrap();
+        let mut text = char.to_string();
         // if the char is a space, add the next one straight away
         // ...pausing on spaces makes it look choppy...
-        if char == b' ' {
-            if index + 1 < self.entire_text.len() {
-                let next_char = self.entire_text.as_bytes()[index + 1];
-                bytes.push(next_char);
+        if char == ' ' {
+            if let Some(next_char) = chars.next() {
+                text.push(next_char);
             }
         }
 
-        let text = from_utf8(&bytes).unwrap();
-
-        self.displayed_text.add(TextFragment {
-            text: text.to_string(),
-            color: Some(Color::WHITE),
-            scale: Some(PxScale::from(FONT_SIZE)),
+        let fragments = self.config.font.text(TextFragment {
+            text,
+            color: Some(self.config.text_color),
+            scale: Some(self.config.font_size),
             ..Default::default()
         });
+        for fragment in fragments.fragments() {
+            self.displayed_text.add(fragment.clone());
+        }
 
         // if the text overflows past the bottom of the screen
-        match self.displayed_text.dimensions(&ctx.gfx) {
-            Some(r) => {
-                let max_height = self.image.height() as f32 - (2.0 * VERTICAL_PADDING);
-
-                if r.h >= max_height {
-                    // find first space from the end
-                    let mut i = index;
-                    while self.entire_text.as_bytes()[i] != b' ' {
-                        i -= 1;
-                    }
-
-                    self.entire_text = self.entire_text[(i + 1)..].to_string();
-                    self.displayed_text = Text::new("");
-                    self.displayed_text.set_bounds(self.bounds);
-                }
-            }
-            None => {}
+        let r = ctx.measure(&self.displayed_text);
+        let max_height = self.image_height(&ctx.screen()) - (2.0 * self.config.vertical_padding);
+
+        if r.h >= max_height {
+            // continue from the last word that fit, or from this char if there are no spaces
+            // (like in text without spaces between words)
+            let start = match self.entire_text[..index].rfind(' ') {
+                Some(i) => i + 1,
+                None => index,
+            };
+
+            self.entire_text = self.entire_text[start..].to_string();
+            self.displayed_text = Text::new("");
+            self.displayed_text.set_bounds(self.bounds);
         }
     }
 }
@@ -0,0 +1,338 @@
+use std::path::Path;
+
+use crate::graphics::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    canvas::{
+        FormConfig, ImageConfig, InputTextConfig, LayoutConfig, ProgressConfig, SelectConfig,
+    },
+    countdown::CountdownConfig,
+    font::FontsConfig,
+    notifications::NotificationConfig,
+    screen::Screen,
+    textbox::TextboxConfig,
+};
+
+// Every widget's config in one place. Theme files only need to contain the keys they change,
+// everything else comes from the base theme (light, unless the file has "base" = "dark")
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub fonts: FontsConfig,
+    pub textbox: TextboxConfig,
+    pub input_text: InputTextConfig,
+    pub select: SelectConfig,
+    pub image: ImageConfig,
+    pub layout: LayoutConfig,
+    pub form: FormConfig,
+    pub progress: ProgressConfig,
+    pub notification: NotificationConfig,
+    pub countdown: CountdownConfig,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::light()
+    }
+}
+
+impl Theme {
+    // black text on white backgrounds
+    pub fn light() -> Self {
+        Self {
+            fonts: Default::default(),
+            textbox: Default::default(),
+            input_text: Default::default(),
+            select: Default::default(),
+            image: Default::default(),
+            layout: Default::default(),
+            form: Default::default(),
+            progress: Default::default(),
+            notification: Default::default(),
+            countdown: Default::default(),
+        }
+    }
+
+    // white text on dark grey backgrounds
+    pub fn dark() -> Self {
+        let text_color = Color::from_rgb(230, 230, 230);
+        let background_color = Color::from_rgb(30, 30, 34);
+        let outline_color = Color::from_rgb(110, 110, 120);
+
+        let mut theme = Self::light();
+
+        theme.input_text.text_color = text_color;
+        theme.input_text.background_color = background_color;
+        theme.input_text.background_outline_color = outline_color;
+
+        theme.select.text_color = text_color;
+        theme.select.background_color = background_color;
+        theme.select.background_outline_color = outline_color;
+        theme.select.selected_option_background_color = Color::from_rgba(255, 255, 255, 50);
+
+        theme.image.text_color = text_color;
+        theme.image.background_color = background_color;
+        theme.image.background_outline_color = outline_color;
+
+        theme.form.text_color = text_color;
+        theme.form.error_color = Color::from_rgb(255, 100, 100);
+        theme.form.background_color = background_color;
+        theme.form.background_outline_color = outline_color;
+
+        theme.progress.text_color = text_color;
+        theme.progress.bar_background_color = Color::from_rgba(255, 255, 255, 40);
+        theme.progress.background_color = background_color;
+        theme.progress.background_outline_color = outline_color;
+
+        theme.notification.text_color = text_color;
+        theme.notification.background_color = background_color;
+        theme.notification.background_outline_color = outline_color;
+
+        theme
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "light" => Some(Self::light()),
+            "dark" => Some(Self::dark()),
+            _ => None,
+        }
+    }
+
+    // Loads a theme from a .toml or .json file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read theme {}: {}", path.display(), e))?;
+
+        let value: serde_json::Value = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(&contents).map_err(|e| e.to_string())?,
+            _ => toml::from_str(&contents).map_err(|e| e.to_string())?,
+        };
+
+        let base = match value.get("base") {
+            Some(serde_json::Value::String(name)) => {
+                Self::builtin(name).ok_or(format!("Unknown base theme {}", name))?
+            }
+            _ => Self::light(),
+        };
+
+        base.with_overrides(&value)
+    }
+
+    // Makes a copy of the theme with the keys in overrides changed (used for per-message themes)
+    pub fn with_overrides(&self, overrides: &serde_json::Value) -> Result<Self, String> {
+        let mut value = serde_json::to_value(self).unwrap();
+        merge_json(&mut value, overrides);
+
+        serde_json::from_value(value).map_err(|e| format!("Invalid theme: {}", e))
+    }
+
+    // Makes a copy of the theme for the screen: every size is multiplied by its scale, and
+    // the canvas widgets are centered on it
+    pub fn for_screen(&self, screen: &Screen) -> Self {
+        let mut value = serde_json::to_value(self).unwrap();
+        scale_sizes(&mut value, screen.scale as f64);
+
+        let mut theme: Self = serde_json::from_value(value).unwrap();
+        let (x, y) = (screen.center_x(), screen.center_y());
+        (theme.input_text.x_position, theme.input_text.y_position) = (x, y);
+        (theme.select.x_position, theme.select.y_position) = (x, y);
+        (theme.image.x_position, theme.image.y_position) = (x, y);
+        (theme.layout.x_position, theme.layout.y_position) = (x, y);
+        (theme.form.x_position, theme.form.y_position) = (x, y);
+        (theme.progress.x_position, theme.progress.y_position) = (x, y);
+
+        theme.fit_widths(screen.width);
+        theme
+    }
+
+    // Keeps the canvas widgets from getting wider than the screen, which a corner window is
+    // smaller than the default sizes are made for
+    fn fit_widths(&mut self, width: f32) {
+        let input_text = &mut self.input_text;
+        input_text.text_max_width = input_text
+            .text_max_width
+            .min(width - 2.0 * input_text.background_outline_width);
+        input_text.text_min_width = input_text.text_min_width.min(input_text.text_max_width);
+
+        let image = &mut self.image;
+        image.max_width = image
+            .max_width
+            .min(width - 2.0 * (image.padding + image.background_outline_width));
+
+        let progress = &mut self.progress;
+        progress.bar_width = progress
+            .bar_width
+            .min(width - 2.0 * (progress.padding + progress.background_outline_width));
+    }
+
+    // The configs with their fonts filled in, and for widgets that contain other widgets,
+    // also those widgets' configs
+
+    pub fn textbox_config(&self) -> TextboxConfig {
+        TextboxConfig {
+            font: self.fonts.textbox(),
+            ..self.textbox.clone()
+        }
+    }
+
+    pub fn input_text_config(&self) -> InputTextConfig {
+        InputTextConfig {
+            font: self.fonts.input(),
+            ..self.input_text.clone()
+        }
+    }
+
+    pub fn select_config(&self) -> SelectConfig {
+        SelectConfig {
+            input_text_config: self.input_text_config(),
+            option_font: self.fonts.options(),
+            ..self.select.clone()
+        }
+    }
+
+    pub fn image_config(&self) -> ImageConfig {
+        ImageConfig {
+            font: self.fonts.default.clone(),
+            ..self.image.clone()
+        }
+    }
+
+    pub fn form_config(&self) -> FormConfig {
+        FormConfig {
+            input_text_config: self.input_text_config(),
+            select_config: self.select_config(),
+            font: self.fonts.default.clone(),
+            ..self.form.clone()
+        }
+    }
+
+    pub fn progress_config(&self) -> ProgressConfig {
+        ProgressConfig {
+            font: self.fonts.default.clone(),
+            ..self.progress.clone()
+        }
+    }
+
+    pub fn notification_config(&self) -> NotificationConfig {
+        NotificationConfig {
+            font: self.fonts.default.clone(),
+            ..self.notification.clone()
+        }
+    }
+
+    pub fn layout_config(&self) -> LayoutConfig {
+        LayoutConfig {
+            input_text_config: self.input_text_config(),
+            select_config: self.select_config(),
+            image_config: self.image_config(),
+            form_config: self.form_config(),
+            progress_config: self.progress_config(),
+            ..self.layout.clone()
+        }
+    }
+}
+
+// Recursively replaces the keys in base with the ones in overrides
+fn merge_json(base: &mut serde_json::Value, overrides: &serde_json::Value) {
+    match (base, overrides) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(key) {
+                    Some(base_value) => merge_json(base_value, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+
+        (base, overrides) => *base = overrides.clone(),
+    }
+}
+
+// The keys of everything in a theme that's a size (in 1080p pixels), which are the only things
+// scaled. Anything else (alphas, counts, durations, colors) keeps its value, so a new size has to be added here
+const SIZE_KEYS: [&str; 28] = [
+    "avatar_size",
+    "background_outline_width",
+    "bar_height",
+    "bar_width",
+    "caption_font_size",
+    "caption_spacing",
+    "checkbox_size",
+    "focus_outline_width",
+    "font_size",
+    "horizontal_padding",
+    "label_font_size",
+    "label_spacing",
+    "label_width",
+    "margin",
+    "max_height",
+    "max_width",
+    "padding",
+    "row_spacing",
+    "spacing",
+    "spinner_dot_size",
+    "spinner_radius",
+    "text_font_size",
+    "text_horizontal_padding",
+    "text_max_width",
+    "text_min_width",
+    "text_vertical_padding",
+    "vertical_padding",
+    "width",
+];
+
+fn scale_sizes(value: &mut serde_json::Value, scale: f64) {
+    let map = match value {
+        serde_json::Value::Object(map) => map,
+        _ => return,
+    };
+
+    for (key, value) in map {
+        match value {
+            serde_json::Value::Number(number) if SIZE_KEYS.contains(&key.as_str()) => {
+                if let Some(size) = number.as_f64() {
+                    *value = (size * scale).into();
+                }
+            }
+
+            value => scale_sizes(value, scale),
+        }
+    }
+}
+
+// Colors in themes are written as "#rrggbb" or "#rrggbbaa"
+pub mod color {
+    use crate::graphics::Color;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        let (r, g, b, a) = color.to_rgba();
+        serializer.serialize_str(&format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        let hex = string.trim_start_matches('#');
+
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or(D::Error::custom(format!("Invalid color {}", string)))
+        };
+
+        match hex.len() {
+            6 => Ok(Color::from_rgb(channel(0)?, channel(2)?, channel(4)?)),
+            8 => Ok(Color::from_rgba(
+                channel(0)?,
+                channel(2)?,
+                channel(4)?,
+                channel(6)?,
+            )),
+            _ => Err(D::Error::custom(format!("Invalid color {}", string))),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+#[cfg(feature = "ggez")]
+use ggez::{
+    conf::WindowMode,
+    winit::{
+        dpi::{PhysicalPosition, PhysicalSize},
+        window::WindowLevel,
+    },
+    Context,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::screen::Screen;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowPlacement {
+    // a transparent borderless window covering a whole monitor
+    #[default]
+    Overlay,
+    // a normal window with decorations
+    Windowed,
+    // a transparent borderless window in a corner of a monitor, with just the avatar and textbox
+    Corner,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    pub placement: WindowPlacement,
+    // index of the monitor to use, if not given the one the window opens on is used
+    pub monitor: Option<usize>,
+    // position of a windowed window, relative to the top left of the monitor
+    pub position: Option<[i32; 2]>,
+    // size of a windowed window (in pixels), or of the corner (in 1080p pixels, like theme sizes)
+    pub size: Option<[u32; 2]>,
+    pub corner: Corner,
+    pub always_on_top: bool,
+}
+
+#[cfg(feature = "ggez")]
+const DEFAULT_WINDOWED_SIZE: [u32; 2] = [1280, 720];
+const DEFAULT_CORNER_SIZE: [u32; 2] = [1000, 450];
+
+impl WindowConfig {
+    // The screen a corner window on the monitor draws on. The corner keeps the monitor's scale,
+    // so things are as big as they'd be in an overlay
+    pub fn corner_screen(&self, monitor: &Screen) -> Screen {
+        let [width, height] = self.size.unwrap_or(DEFAULT_CORNER_SIZE);
+
+        Screen {
+            width: (width as f32 * monitor.scale).min(monitor.width),
+            height: (height as f32 * monitor.scale).min(monitor.height),
+            ..*monitor
+        }
+    }
+}
+
+#[cfg(feature = "ggez")]
+impl WindowConfig {
+    // The window starts hidden, it's shown by place once it's where it should be
+    pub fn window_mode(&self) -> WindowMode {
+        let mode = WindowMode::default().visible(false);
+
+        match self.placement {
+            WindowPlacement::Windowed => {
+                let [width, height] = self.size.unwrap_or(DEFAULT_WINDOWED_SIZE);
+                mode.dimensions(width as f32, height as f32)
+            }
+
+            WindowPlacement::Overlay | WindowPlacement::Corner => {
+                mode.transparent(true).borderless(true)
+            }
+        }
+    }
+
+    // Moves and resizes the window onto its monitor and shows it, returning the screen that's drawn on
+    pub fn place(&self, ctx: &Context) -> Screen {
+        let window = ctx.gfx.window();
+
+        let monitor = self
+            .monitor
+            .and_then(|i| window.available_monitors().nth(i))
+            .or_else(|| window.current_monitor());
+
+        let (monitor_position, monitor_size, dpi_scale) = match &monitor {
+            Some(monitor) => (
+                monitor.position(),
+                monitor.size(),
+                monitor.scale_factor() as f32,
+            ),
+            None => (PhysicalPosition::new(0, 0), window.inner_size(), 1.0),
+        };
+        let monitor_screen = Screen::new(
+            monitor_size.width as f32,
+            monitor_size.height as f32,
+            dpi_scale,
+        );
+
+        let screen = match self.placement {
+            WindowPlacement::Overlay => {
+                window.set_outer_position(monitor_position);
+                window.set_inner_size(monitor_size);
+
+                monitor_screen
+            }
+
+            WindowPlacement::Windowed => {
+                if let Some([x, y]) = self.position {
+                    window.set_outer_position(PhysicalPosition::new(
+                        monitor_position.x + x,
+                        monitor_position.y + y,
+                    ));
+                }
+
+                let [width, height] = self.size.unwrap_or(DEFAULT_WINDOWED_SIZE);
+                Screen::new(width as f32, height as f32, dpi_scale)
+            }
+
+            WindowPlacement::Corner => {
+                let corner_screen = self.corner_screen(&monitor_screen);
+                let (width, height) = (corner_screen.width, corner_screen.height);
+
+                let x = match self.corner {
+                    Corner::TopLeft | Corner::BottomLeft => 0.0,
+                    Corner::TopRight | Corner::BottomRight => monitor_screen.width - width,
+                };
+                let y = match self.corner {
+                    Corner::TopLeft | Corner::TopRight => 0.0,
+                    Corner::BottomLeft | Corner::BottomRight => monitor_screen.height - height,
+                };
+
+                window.set_outer_position(PhysicalPosition::new(
+                    monitor_position.x + x as i32,
+                    monitor_position.y + y as i32,
+                ));
+                window.set_inner_size(PhysicalSize::new(width as u32, height as u32));
+
+                corner_screen
+            }
+        };
+
+        if self.always_on_top {
+            window.set_window_level(WindowLevel::AlwaysOnTop);
+        }
+
+        window.set_visible(true);
+
+        screen
+    }
+}
@@ -0,0 +1,63 @@
+use std::{
+    io::{self, Write},
+    rc::Rc,
+    sync::mpsc::channel,
+};
+
+use joshu_core::{
+    app::App,
+    assets::Assets,
+    clock::ManualClock,
+    context::Context,
+    graphics::MonospaceMeasurer,
+    input::{InputEvent, Key},
+    screen::Screen,
+    theme::Theme,
+};
+
+// Like stdout once whatever was reading it has exited
+struct ClosedPipe;
+
+impl Write for ClosedPipe {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn closed_output_doesnt_stop_the_app() {
+    let measurer = MonospaceMeasurer;
+    let ctx = Context::new(&measurer, Rc::new(ManualClock::new()), Screen::default());
+    let (sender, receiver) = channel();
+
+    let mut app = App::new(
+        &ctx,
+        receiver,
+        Box::new(ClosedPipe),
+        Assets::blank(),
+        Theme::light(),
+    );
+
+    for id in ["first", "second"] {
+        sender
+            .send(format!(
+                r#"{{ "id": "{}", "canvas_mode": "InputText" }}"#,
+                id
+            ))
+            .unwrap();
+    }
+
+    app.update(&ctx);
+    app.handle_input(&ctx, InputEvent::Key(Key::Enter));
+    app.update(&ctx);
+    app.update(&ctx);
+    app.handle_input(&ctx, InputEvent::Key(Key::Enter));
+    app.update(&ctx);
+    app.update(&ctx);
+
+    assert!(app.quit_requested());
+}
@@ -0,0 +1,59 @@
+use std::{env, fs, process};
+
+use joshu_core::{completion::complete_path, headless::Headless, input::Key};
+
+#[test]
+fn paths_are_completed() {
+    let dir = env::temp_dir().join(format!("joshu-completion-{}", process::id()));
+    fs::create_dir_all(dir.join("notes")).unwrap();
+    fs::write(dir.join("novel.txt"), "").unwrap();
+    fs::write(dir.join(".notes"), "").unwrap();
+    fs::write(dir.join("todo.txt"), "").unwrap();
+
+    let text = format!("open {}/no", dir.display());
+    assert_eq!(
+        complete_path(&text),
+        vec![
+            format!("open {}/notes/", dir.display()),
+            format!("open {}/novel.txt", dir.display()),
+        ]
+    );
+
+    // hidden ones only once a . is typed
+    let text = format!("{}/.no", dir.display());
+    assert_eq!(
+        complete_path(&text),
+        vec![format!("{}/.notes", dir.display())]
+    );
+
+    assert!(complete_path(&format!("{}/x", dir.display())).is_empty());
+    assert!(complete_path("open ").is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn completions_are_drawn_under_the_input() {
+    let mut joshu = Headless::new();
+    joshu
+        .send(r#"{ "id": 1, "canvas_mode": "InputText", "completion": "Plugin" }"#)
+        .unwrap();
+    joshu.type_text("git ch");
+    joshu.press(Key::Tab);
+    joshu
+        .send(
+            r#"{ "kind": "Completions", "id": 1, "options": ["git checkout", "git cherry-pick"] }"#,
+        )
+        .unwrap();
+
+    let texts = joshu.draw().texts();
+    assert!(texts.contains(&String::from("git ch")));
+    assert!(texts.contains(&String::from("git checkout")));
+    assert!(texts.contains(&String::from("git cherry-pick")));
+
+    // and they're gone once one is picked
+    joshu.press(Key::Enter);
+    let texts = joshu.draw().texts();
+    assert!(texts.contains(&String::from("git checkout")));
+    assert!(!texts.contains(&String::from("git cherry-pick")));
+}
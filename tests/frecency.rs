@@ -0,0 +1,35 @@
+use std::{env, fs, process};
+
+use joshu_core::frecency::Frecency;
+
+const DAY: u64 = 24 * 60 * 60;
+
+#[test]
+fn recent_picks_count_for_more() {
+    let now = 100 * DAY;
+    let mut frecency = Frecency::default();
+
+    for _ in 0..3 {
+        frecency.record("apps", "old", now - 60 * DAY);
+    }
+    frecency.record("apps", "new", now);
+
+    let scores = frecency.scores("apps", now);
+    assert!(scores["new"] > scores["old"]);
+    assert!(frecency.scores("other", now).is_empty());
+}
+
+#[test]
+fn picks_are_saved_and_loaded_again() {
+    let dir = env::temp_dir().join(format!("joshu-frecency-{}", process::id()));
+    let path = dir.join("frecency.json");
+
+    let mut frecency = Frecency::load(&path);
+    frecency.record("apps", "editor", 0);
+    frecency.record("apps", "editor", 0);
+
+    let frecency = Frecency::load(&path);
+    assert_eq!(frecency.scores("apps", 0)["editor"], 8.0);
+
+    fs::remove_dir_all(dir).unwrap();
+}
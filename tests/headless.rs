@@ -0,0 +1,263 @@
+use std::time::Duration;
+
+use joshu_core::{headless::Headless, input::Key, message::Protocol};
+use serde_json::json;
+
+#[test]
+fn input_text_responds_with_typed_text() {
+    let mut joshu = Headless::new();
+
+    joshu
+        .send(r#"{ "id": "name", "canvas_mode": "InputText" }"#)
+        .unwrap();
+    joshu.type_text("Kurisu");
+    joshu.press(Key::Backspace);
+    joshu.press(Key::Enter);
+
+    assert_eq!(
+        joshu.responses(),
+        vec![json!({ "id": "name", "data": "Kuris" })]
+    );
+}
+
+#[test]
+fn typed_text_is_drawn() {
+    let mut joshu = Headless::new();
+
+    joshu.send(r#"{ "canvas_mode": "InputText" }"#).unwrap();
+    assert!(joshu
+        .draw()
+        .texts()
+        .contains(&String::from("Enter text...")));
+
+    joshu.type_text("hello");
+    assert!(joshu.draw().texts().contains(&String::from("hello")));
+}
+
+#[test]
+fn select_filters_options() {
+    let mut joshu = Headless::new();
+
+    joshu
+        .send(r#"{ "id": "fruit", "canvas_mode": { "Select": ["apple", "banana", "cherry"] } }"#)
+        .unwrap();
+    joshu.type_text("ERR");
+    joshu.press(Key::Enter);
+
+    assert_eq!(
+        joshu.responses(),
+        vec![json!({ "id": "fruit", "data": "cherry" })]
+    );
+}
+
+#[test]
+fn select_arrow_keys_move_selection() {
+    let mut joshu = Headless::new();
+
+    joshu
+        .send(r#"{ "id": "fruit", "canvas_mode": { "Select": ["apple", "banana", "cherry"] } }"#)
+        .unwrap();
+    joshu.press(Key::Down);
+    joshu.press(Key::Down);
+    joshu.press(Key::Up);
+    joshu.press(Key::Enter);
+
+    assert_eq!(
+        joshu.responses(),
+        vec![json!({ "id": "fruit", "data": "banana" })]
+    );
+}
+
+#[test]
+fn commands_are_queued_and_quit_after_the_last() {
+    let mut joshu = Headless::new();
+
+    joshu
+        .send(r#"{ "id": "first", "canvas_mode": "InputText" }"#)
+        .unwrap();
+    joshu
+        .send(r#"{ "id": "second", "canvas_mode": "InputText" }"#)
+        .unwrap();
+
+    joshu.type_text("a");
+    joshu.press(Key::Enter);
+    assert_eq!(
+        joshu.responses(),
+        vec![json!({ "id": "first", "data": "a" })]
+    );
+    assert!(!joshu.quit_requested());
+
+    // the queued command starts on the next frame
+    joshu.frame();
+    joshu.type_text("b");
+    joshu.press(Key::Enter);
+    assert_eq!(
+        joshu.responses(),
+        vec![json!({ "id": "second", "data": "b" })]
+    );
+
+    joshu.frame();
+    assert!(joshu.quit_requested());
+}
+
+#[test]
+fn textbox_finishes_after_text_is_shown() {
+    let mut joshu = Headless::new();
+
+    joshu
+        .send(r#"{ "id": "hi", "textbox_text": "Hello there" }"#)
+        .unwrap();
+
+    joshu.advance(Duration::from_millis(1000));
+    assert!(joshu.responses().is_empty());
+
+    joshu.advance(Duration::from_millis(2000));
+    assert_eq!(
+        joshu.responses(),
+        vec![json!({ "id": "hi", "data": "Finished displaying text" })]
+    );
+}
+
+#[test]
+fn escape_quits() {
+    let mut joshu = Headless::new();
+
+    joshu
+        .send(r#"{ "id": "name", "canvas_mode": "InputText" }"#)
+        .unwrap();
+    joshu.press(Key::Escape);
+
+    assert_eq!(
+        joshu.responses(),
+        vec![json!({ "id": "name", "data": "Quitting..." })]
+    );
+    assert!(joshu.quit_requested());
+}
+
+#[test]
+fn update_without_executing_command_is_an_error() {
+    let mut joshu = Headless::new();
+
+    joshu
+        .send(r#"{ "kind": "Update", "id": "missing", "textbox_text": "hi" }"#)
+        .unwrap();
+
+    assert_eq!(
+        joshu.responses(),
+        vec![json!({
+            "id": "missing",
+            "data": null,
+            "error": "No command with id missing is executing"
+        })]
+    );
+}
+
+#[test]
+fn closing_input_waits_for_the_executing_command() {
+    let mut joshu = Headless::new();
+
+    joshu
+        .send(r#"{ "id": "name", "canvas_mode": "InputText" }"#)
+        .unwrap();
+    joshu.close_input();
+    assert!(!joshu.quit_requested());
+    assert!(joshu.send(r#"{ "id": "late" }"#).is_err());
+
+    joshu.press(Key::Enter);
+    assert_eq!(joshu.responses(), vec![json!({ "id": "name", "data": "" })]);
+    assert!(joshu.quit_requested());
+}
+
+#[test]
+fn closing_input_before_any_command_quits() {
+    let mut joshu = Headless::new();
+
+    joshu.close_input();
+    assert!(joshu.quit_requested());
+}
+
+#[test]
+fn debug_overlay_shows_what_joshu_is_doing() {
+    let mut joshu = Headless::new();
+
+    joshu
+        .send(r#"{ "id": "first", "canvas_mode": "InputText" }"#)
+        .unwrap();
+    joshu.send_line("not json").unwrap();
+    joshu.advance(Duration::from_secs(1));
+
+    // the frames in the last second at 16ms each, and the one after pressing F12
+    let overlay_shown = |joshu: &Headless| {
+        joshu
+            .draw()
+            .texts()
+            .iter()
+            .any(|text| text.contains("id: first\nqueue: 0\nfps: 64\ninput: not json"))
+    };
+    assert!(!overlay_shown(&joshu));
+
+    joshu.press(Key::F12);
+    assert!(overlay_shown(&joshu));
+}
+
+#[test]
+fn notifications_are_drawn_until_they_are_dismissed() {
+    let mut joshu = Headless::new();
+
+    joshu
+        .send(r#"{ "id": "hi", "canvas_mode": "InputText" }"#)
+        .unwrap();
+    joshu
+        .send(r#"{ "kind": "Notification", "textbox_text": "Battery at 5%" }"#)
+        .unwrap();
+    joshu.press(Key::Enter);
+    assert_eq!(joshu.responses(), vec![json!({ "id": "hi", "data": "" })]);
+
+    joshu.advance(Duration::from_millis(3900));
+    assert!(joshu
+        .draw()
+        .texts()
+        .contains(&String::from("Battery at 5%")));
+    assert!(!joshu.quit_requested());
+
+    joshu.advance(Duration::from_millis(100));
+    assert!(joshu.draw().texts().is_empty());
+    assert!(joshu.quit_requested());
+}
+
+#[test]
+fn jsonrpc_describe_lists_methods() {
+    let mut joshu = Headless::new();
+    joshu.set_protocol(Protocol::JsonRpc);
+
+    joshu
+        .send(r#"{ "jsonrpc": "2.0", "id": 1, "method": "describe" }"#)
+        .unwrap();
+
+    let responses = joshu.responses();
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0]["id"], json!(1));
+    assert_eq!(
+        responses[0]["result"]["methods"],
+        json!(["show", "prompt", "select", "describe", "quit"])
+    );
+    assert_eq!(
+        responses[0]["result"]["avatar_emotions"][0],
+        json!("normal")
+    );
+}
+
+#[test]
+fn spinner_without_dots_or_steps_is_drawn() {
+    let mut joshu = Headless::new();
+
+    joshu
+        .send(
+            r#"{ "canvas_mode": { "Progress": { "label": "Building" } },
+                 "theme": { "progress": { "spinner_dots": 0, "spinner_step_duration": 0 } } }"#,
+        )
+        .unwrap();
+    joshu.advance(Duration::from_millis(250));
+
+    assert!(joshu.draw().texts().contains(&String::from("Building")));
+}
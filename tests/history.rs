@@ -0,0 +1,63 @@
+use std::{env, fs, process};
+
+use joshu_core::{
+    headless::Headless,
+    history::History,
+    input::{Key, Shortcut},
+};
+use serde_json::json;
+
+#[test]
+fn history_is_saved_and_loaded_again() {
+    let dir = env::temp_dir().join(format!("joshu-history-{}", process::id()));
+    let path = dir.join("history.json");
+
+    let mut joshu = Headless::new();
+    joshu.set_history(History::load(&path));
+    joshu
+        .send(r#"{ "id": 1, "canvas_mode": "InputText", "history_key": "notes" }"#)
+        .unwrap();
+    joshu.type_text("buy milk");
+    joshu.press(Key::Enter);
+    assert_eq!(
+        joshu.responses(),
+        vec![json!({ "id": 1, "data": "buy milk" })]
+    );
+
+    // like joshu starting again
+    let mut joshu = Headless::new();
+    joshu.set_history(History::load(&path));
+    joshu
+        .send(r#"{ "id": 2, "canvas_mode": "InputText", "history_key": "notes" }"#)
+        .unwrap();
+    joshu.press(Key::Up);
+    joshu.press(Key::Enter);
+    assert_eq!(
+        joshu.responses(),
+        vec![json!({ "id": 2, "data": "buy milk" })]
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn search_query_is_drawn() {
+    let mut history = History::default();
+    history.add("notes", "buy milk");
+
+    let mut joshu = Headless::new();
+    joshu.set_history(history);
+    joshu
+        .send(r#"{ "canvas_mode": "InputText", "history_key": "notes" }"#)
+        .unwrap();
+    joshu.shortcut(Shortcut::SearchHistory);
+    joshu.type_text("mil");
+    assert!(joshu.draw().texts().contains(&String::from("search: mil")));
+    assert!(joshu.draw().texts().contains(&String::from("buy milk")));
+
+    joshu.type_text("x");
+    assert!(joshu
+        .draw()
+        .texts()
+        .contains(&String::from("no match: milx")));
+}
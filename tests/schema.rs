@@ -0,0 +1,32 @@
+use joshu_core::schema::{deprecations, json_schema};
+use serde_json::json;
+
+#[test]
+fn schema_has_messages_and_responses() {
+    let schema = json_schema();
+    let definitions = &schema["definitions"];
+
+    assert!(definitions["Message"]["properties"]["protocol_version"].is_object());
+    assert!(definitions["Response"]["properties"]["warnings"].is_object());
+    // Image's own timeout is still in there, marked as deprecated
+    assert_eq!(
+        definitions["ImageData"]["properties"]["timeout_ms"]["deprecated"],
+        json!(true)
+    );
+}
+
+#[test]
+fn deprecations_are_found_in_layouts() {
+    let message = json!({
+        "canvas_mode": { "Layout": { "children": [
+            { "id": "a", "mode": "InputText" },
+            { "id": "b", "mode": { "Image": { "path": "a.png", "timeout_ms": 500 } } },
+        ] } }
+    });
+
+    assert_eq!(deprecations(&message).len(), 1);
+    assert!(deprecations(&json!({ "timeout_ms": 500, "canvas_mode": { "Image": {} } })).is_empty());
+    // the forms the series started with aren't deprecated
+    assert!(deprecations(&json!({ "kind": "SetOptions", "options": [] })).is_empty());
+    assert!(deprecations(&json!({ "canvas_mode": { "Select": ["apple"] } })).is_empty());
+}
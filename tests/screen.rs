@@ -0,0 +1,59 @@
+use joshu_core::{
+    graphics::{DrawCommand, Rect},
+    headless::Headless,
+    screen::Screen,
+    theme::Theme,
+};
+
+// The middle of everything that's drawn as a rect (rounded, since the sizes are scaled)
+fn rects_center_x(joshu: &Headless) -> f32 {
+    let rects: Vec<Rect> = joshu
+        .draw()
+        .commands()
+        .iter()
+        .filter_map(|c| match c {
+            DrawCommand::Rect { rect, .. } => Some(*rect),
+            _ => None,
+        })
+        .collect();
+
+    let left = rects.iter().map(|r| r.x).fold(f32::MAX, f32::min);
+    let right = rects.iter().map(|r| r.x + r.w).fold(f32::MIN, f32::max);
+    ((left + right) / 2.0).round()
+}
+
+#[test]
+fn the_layout_is_scaled_to_the_height() {
+    assert_eq!(Screen::parse("1920x1080").unwrap().scale, 1.0);
+    assert_eq!(Screen::parse("1366x768").unwrap().scale, 768.0 / 1080.0);
+}
+
+#[test]
+fn a_4k_screen_is_scaled_up_whatever_its_dpi_scale() {
+    let screen = Screen::parse("3840x2160").unwrap();
+    assert_eq!(screen.scale, 2.0);
+    assert_eq!(screen.dpi_scale, 1.0);
+
+    let screen = Screen::parse("3840x2160@2").unwrap();
+    assert_eq!(screen.scale, 2.0);
+    assert_eq!(screen.dpi_scale, 2.0);
+}
+
+#[test]
+fn invalid_screens_are_rejected() {
+    assert_eq!(Screen::parse("3840"), None);
+    assert_eq!(Screen::parse("0x1080"), None);
+    assert_eq!(Screen::parse("3840x2160@0"), None);
+}
+
+#[test]
+fn every_app_has_its_own_screen() {
+    let mut small = Headless::with_screen(Theme::light(), Screen::parse("1366x768").unwrap());
+    let mut big = Headless::new();
+    for joshu in [&mut small, &mut big] {
+        joshu.send(r#"{ "canvas_mode": "InputText" }"#).unwrap();
+    }
+
+    assert_eq!(rects_center_x(&small), 683.0);
+    assert_eq!(rects_center_x(&big), 960.0);
+}
@@ -0,0 +1,31 @@
+use std::{fs, path::Path};
+
+use joshu_core::headless::run_script;
+
+// Runs every script in tests/scripts (see run_script for what they look like)
+#[test]
+fn scripts() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/scripts");
+
+    let mut paths = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "txt"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert!(!paths.is_empty(), "No scripts in {}", dir.display());
+
+    let failures = paths
+        .iter()
+        .filter_map(|path| {
+            let script = fs::read_to_string(path).unwrap();
+            let name = path.file_name().unwrap().to_string_lossy();
+
+            run_script(&script)
+                .err()
+                .map(|e| format!("{}: {}", name, e))
+        })
+        .collect::<Vec<_>>();
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
@@ -0,0 +1,66 @@
+use std::{rc::Rc, sync::mpsc::channel, time::Duration};
+
+use joshu_core::{
+    app::FinishedMessage,
+    clock::ManualClock,
+    context::Context,
+    graphics::{DrawList, Image, MonospaceMeasurer},
+    screen::Screen,
+    textbox::{Textbox, TextboxConfig},
+};
+
+fn displayed_text(ctx: &Context, textbox: &Textbox) -> String {
+    let mut draw_list = DrawList::default();
+    textbox.draw(ctx, &mut draw_list);
+    draw_list.texts().concat()
+}
+
+#[test]
+fn text_appears_one_char_at_a_time_then_disappears() {
+    let clock = Rc::new(ManualClock::new());
+    let measurer = MonospaceMeasurer;
+    let ctx = Context::new(&measurer, clock.clone(), Screen::default());
+    let (sender, receiver) = channel();
+
+    let config = TextboxConfig {
+        text_animation_speed: 30,
+        disappear_speed: 2000,
+        ..Default::default()
+    };
+    let mut textbox = Textbox::new(&ctx, &config, Image::blank(1920, 328), 600.0, sender);
+    textbox.set_text("Hi you", true);
+
+    textbox.update(&ctx);
+    assert_eq!(displayed_text(&ctx, &textbox), "");
+
+    clock.advance(Duration::from_millis(29));
+    textbox.update(&ctx);
+    assert_eq!(displayed_text(&ctx, &textbox), "");
+
+    clock.advance(Duration::from_millis(1));
+    textbox.update(&ctx);
+    assert_eq!(displayed_text(&ctx, &textbox), "H");
+
+    // spaces come with the char after them
+    for _ in 0..2 {
+        clock.advance(Duration::from_millis(30));
+        textbox.update(&ctx);
+    }
+    assert_eq!(displayed_text(&ctx, &textbox), "Hi y");
+
+    for _ in 0..3 {
+        clock.advance(Duration::from_millis(30));
+        textbox.update(&ctx);
+    }
+    assert_eq!(displayed_text(&ctx, &textbox), "Hi you");
+    assert!(receiver.try_recv().is_err());
+
+    clock.advance(Duration::from_millis(1999));
+    textbox.update(&ctx);
+    assert!(receiver.try_recv().is_err());
+
+    clock.advance(Duration::from_millis(1));
+    textbox.update(&ctx);
+    assert!(matches!(receiver.try_recv(), Ok(FinishedMessage::Textbox)));
+    assert_eq!(displayed_text(&ctx, &textbox), "");
+}
@@ -0,0 +1,56 @@
+use joshu_core::{graphics::Color, screen::Screen, theme::Theme};
+use serde_json::Value;
+
+// Every float in a theme, by the path of keys to it
+fn floats(value: &Value, path: &str, found: &mut Vec<(String, f64)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                floats(value, &format!("{}.{}", path, key), found);
+            }
+        }
+
+        Value::Number(number) if number.is_f64() => {
+            found.push((path.to_string(), number.as_f64().unwrap()))
+        }
+
+        _ => {}
+    }
+}
+
+#[test]
+fn scaling_only_changes_sizes() {
+    let theme = Theme::default();
+    let scaled = theme.for_screen(&Screen::parse("3840x2160").unwrap());
+    assert_eq!(scaled.textbox.font_size, theme.textbox.font_size * 2.0);
+    assert_eq!(
+        scaled.input_text.text_placeholder_alpha,
+        theme.input_text.text_placeholder_alpha
+    );
+    assert_eq!(
+        scaled.progress.spinner_step_duration,
+        theme.progress.spinner_step_duration
+    );
+    // and the widgets are centered on the screen
+    assert_eq!(scaled.select.x_position, 1920.0);
+    assert_eq!(scaled.select.y_position, 1080.0);
+
+    // a float that's added to the theme has to be added to the sizes too, or be an alpha
+    let (mut before, mut after) = (Vec::new(), Vec::new());
+    floats(&serde_json::to_value(&theme).unwrap(), "", &mut before);
+    floats(&serde_json::to_value(&scaled).unwrap(), "", &mut after);
+    for ((path, size), (_, scaled_size)) in before.iter().zip(&after) {
+        if !path.ends_with("alpha") {
+            assert_eq!(*scaled_size, size * 2.0, "{} wasn't scaled", path);
+        }
+    }
+}
+
+#[test]
+fn colors_are_rounded_when_written() {
+    let mut theme = Theme::default();
+    theme.textbox.text_color = Color::new(0.5, 0.2, 0.999, 1.0);
+
+    let value = serde_json::to_value(&theme).unwrap();
+    assert_eq!(value["textbox"]["text_color"], "#8033ffff");
+}
@@ -0,0 +1,89 @@
+use joshu_core::{
+    graphics::{DrawCommand, MonospaceMeasurer, Rect, TextMeasurer},
+    headless::Headless,
+    screen::Screen,
+    theme::Theme,
+    window::{WindowConfig, WindowPlacement},
+};
+
+// Where everything in the frame is drawn
+fn drawn_rects(joshu: &Headless) -> Vec<(String, Rect)> {
+    joshu
+        .draw()
+        .commands()
+        .iter()
+        .map(|c| match c {
+            DrawCommand::Rect { rect, .. } => (String::from("rect"), *rect),
+            DrawCommand::Text { text, dest } => {
+                let size = MonospaceMeasurer.measure(text);
+                (text.contents(), Rect::new(dest.x, dest.y, size.x, size.y))
+            }
+            DrawCommand::Image { image, dest, scale } => (
+                String::from("image"),
+                Rect::new(
+                    dest.x,
+                    dest.y,
+                    image.width() as f32 * scale.x,
+                    image.height() as f32 * scale.y,
+                ),
+            ),
+        })
+        .collect()
+}
+
+#[test]
+fn nothing_is_drawn_outside_a_corner_window() {
+    let config = WindowConfig {
+        placement: WindowPlacement::Corner,
+        ..Default::default()
+    };
+
+    for monitor in ["1920x1080", "3840x2160", "1366x768"] {
+        let screen = config.corner_screen(&Screen::parse(monitor).unwrap());
+        let options: Vec<String> = (0..20)
+            .map(|i| format!("a long option number {}", i))
+            .collect();
+
+        let messages = [
+            serde_json::json!({ "canvas_mode": "InputText" }),
+            serde_json::json!({ "canvas_mode": { "Select": options } }),
+            serde_json::json!({ "canvas_mode": { "Form": { "fields": [
+                { "id": "name", "label": "A long label for the name", "kind": "Text" },
+                { "id": "age", "label": "Age", "kind": "Number" },
+            ] } } }),
+            serde_json::json!({ "canvas_mode": { "Layout": { "children": [
+                { "id": "name", "mode": "InputText" },
+                { "id": "note", "mode": "InputText" },
+            ] } } }),
+            serde_json::json!({ "canvas_mode": { "Progress": { "percent": 50, "label": "Downloading" } } }),
+            serde_json::json!({ "canvas_mode": { "Image": {
+                "base64": "iVBORw0KGgoAAAANSUhEUgAAAAIAAAACCAYAAABytg0kAAAAEklEQVR4nGP4z8DwH4QZoPR/AEPOB/n6gUr9AAAAAElFTkSuQmCC",
+                "caption": "a caption that goes on ".repeat(10),
+            } } }),
+        ];
+
+        for message in messages {
+            let mut joshu = Headless::with_screen(Theme::light(), screen);
+            joshu.send(&message.to_string()).unwrap();
+            let mut drawn = drawn_rects(&joshu);
+            joshu.type_text(&"a very long answer ".repeat(10));
+            drawn.extend(drawn_rects(&joshu));
+
+            for (what, rect) in drawn {
+                assert!(
+                    rect.x >= 0.0
+                        && rect.y >= 0.0
+                        && rect.x + rect.w <= screen.width
+                        && rect.y + rect.h <= screen.height,
+                    "{} at {:?} is outside the {}x{} corner of a {} monitor, for {}",
+                    what,
+                    rect,
+                    screen.width,
+                    screen.height,
+                    monitor,
+                    message
+                );
+            }
+        }
+    }
+}